@@ -0,0 +1,362 @@
+use crate::{Cell, GameState, GopherSweeper};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// The fraction of cells whose gopher count matches their mirror image: left to
+/// right (`horizontal`), top to bottom (`vertical`) and under a half turn
+/// (`rotational`). A board without cells is fully symmetric and scores 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymmetryScore {
+    pub horizontal: f32,
+    pub vertical: f32,
+    pub rotational: f32,
+}
+
+impl GopherSweeper {
+    /// The Bechtel's Board Benchmark Value: the minimum number of clicks needed to
+    /// clear the board, counting each opening once plus every number outside them.
+    pub fn threebv(&self) -> usize {
+        let (width, height) = self.config.size();
+        let mut result = self.openings().len();
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = &self.field[y][x];
+
+                if cell.has_gopher() || cell.surrounding_gophers == 0 {
+                    continue;
+                }
+
+                let borders_opening = self.surrounding_cells_coords(x, y)
+                    .into_iter()
+                    .any(|(x, y)| !self.field[y][x].has_gopher() && self.field[y][x].surrounding_gophers == 0);
+
+                if !borders_opening {
+                    result += 1;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// A click sequence as long as `threebv`: one cell from each opening, then
+    /// every number outside them, both row by row. The earliest click from which
+    /// the board can be cleared by deduction alone is moved to the front; `None`
+    /// if there is no such click or no layout yet.
+    pub fn optimal_clicks(&self) -> Option<Vec<(usize, usize)>> {
+        if !self.generated { return None }
+
+        let mut openings: Vec<(usize, usize)> = self.openings()
+            .iter()
+            .filter_map(|opening| opening.iter().copied().min_by_key(|&(x, y)| (y, x)))
+            .collect();
+        openings.sort_by_key(|&(x, y)| (y, x));
+
+        let isolated_numbers = self.cells_where(|cell| !cell.has_gopher() && cell.surrounding_gophers > 0)
+            .into_iter()
+            .filter(|&(x, y)| {
+                !self.surrounding_cells_coords(x, y)
+                    .into_iter()
+                    .any(|(x, y)| !self.field[y][x].has_gopher() && self.field[y][x].surrounding_gophers == 0)
+            });
+
+        let mut clicks: Vec<(usize, usize)> = openings.into_iter().chain(isolated_numbers).collect();
+
+        let start = clicks.iter().position(|&(x, y)| {
+            let mut game = self.clone();
+            game.try_expose_cell(x, y);
+            game.solve_logically()
+        })?;
+
+        let first = clicks.remove(start);
+        clicks.insert(0, first);
+
+        Some(clicks)
+    }
+
+    /// Every cell grouped by its `surrounding_gophers`, gopher cells included, each
+    /// group in row-major order. Numbers above 8 (only possible with heavy cells)
+    /// go into the last group.
+    pub fn cells_by_number(&self) -> [Vec<(usize, usize)>; 9] {
+        let mut result: [Vec<(usize, usize)>; 9] = Default::default();
+
+        for (x, y) in self.cells_where(|_| true) {
+            result[(self.field[y][x].surrounding_gophers as usize).min(8)].push((x, y));
+        }
+
+        result
+    }
+
+    /// How many cells show each number, counted like `cells_by_number`.
+    pub fn number_histogram(&self) -> [usize; 9] {
+        self.cells_by_number().map(|cells| cells.len())
+    }
+
+    /// The fraction of safe cells cleared by opening a zero region: the empty cells
+    /// themselves plus the numbers bordering them. 1.0 on a board without gophers.
+    pub fn openness(&self) -> f32 {
+        let is_empty = |x: usize, y: usize| !self.field[y][x].has_gopher() && self.field[y][x].surrounding_gophers == 0;
+
+        let safe = self.cells_where(|cell| !cell.has_gopher());
+        let opened = safe.iter()
+            .filter(|&&(x, y)| is_empty(x, y) || self.surrounding_cells_coords(x, y).into_iter().any(|(x, y)| is_empty(x, y)))
+            .count();
+
+        if safe.is_empty() { 0.0 } else { opened as f32 / safe.len() as f32 }
+    }
+
+    /// `threebv` per safe cell: how many clicks each safe cell costs on average,
+    /// which grows as openings shrink. 0.0 on a board without safe cells.
+    pub fn difficulty_score(&self) -> f32 {
+        let safe = self.field.iter().flatten().filter(|cell| !cell.has_gopher()).count();
+
+        if safe == 0 { 0.0 } else { self.threebv() as f32 / safe as f32 }
+    }
+
+    /// Returns `None` unless the game has been won in a non-zero `elapsed` time.
+    /// The library keeps no clock, so the caller supplies the elapsed time.
+    pub fn threebv_per_second(&self, elapsed: Duration) -> Option<f32> {
+        if self.state() != GameState::Won || elapsed.is_zero() {
+            return None;
+        }
+
+        Some(self.threebv() as f32 / elapsed.as_secs_f32())
+    }
+
+    pub fn symmetry_score(&self) -> SymmetryScore {
+        let (width, height) = self.config.size();
+        let cells = (width * height) as f32;
+
+        let score = |transform: &dyn Fn(usize, usize) -> (usize, usize)| {
+            let matching = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    let (tx, ty) = transform(x, y);
+                    self.field[y][x].gopher_count == self.field[ty][tx].gopher_count
+                })
+                .count();
+
+            if cells == 0.0 { 1.0 } else { matching as f32 / cells }
+        };
+
+        SymmetryScore {
+            horizontal: score(&|x, y| (width - 1 - x, y)),
+            vertical: score(&|x, y| (x, height - 1 - y)),
+            rotational: score(&|x, y| (width - 1 - x, height - 1 - y)),
+        }
+    }
+
+    pub(crate) fn openings(&self) -> Vec<Vec<(usize, usize)>> {
+        self.regions(|cell| !cell.has_gopher() && cell.surrounding_gophers == 0)
+    }
+
+    /// Every connected group of gophers under the configured adjacency, each
+    /// sorted row by row.
+    pub fn gopher_clusters(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut clusters = self.regions(Cell::has_gopher);
+
+        for cluster in &mut clusters {
+            cluster.sort_by_key(|&(x, y)| (y, x));
+        }
+
+        clusters
+    }
+
+    /// Every connected group of unexposed, unflagged cells, sorted row by row,
+    /// paired with the expected number of gophers in it according to
+    /// `safety_estimates`.
+    pub fn region_gopher_expectations(&self) -> Vec<(Vec<(usize, usize)>, f32)> {
+        let safety: HashMap<(usize, usize), f32> = self.safety_estimates().into_iter().collect();
+
+        self.regions(|cell| !cell.is_exposed && !cell.is_flagged)
+            .into_iter()
+            .map(|mut region| {
+                region.sort_by_key(|&(x, y)| (y, x));
+                let expected = region.iter().map(|cell| 1.0 - safety[cell]).sum();
+
+                (region, expected)
+            })
+            .collect()
+    }
+
+    fn regions(&self, member: impl Fn(&Cell) -> bool) -> Vec<Vec<(usize, usize)>> {
+        let (width, height) = self.config.size();
+        let is_member = |x: usize, y: usize| member(&self.field[y][x]);
+
+        let mut visited = vec![vec![false; width]; height];
+        let mut result = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if visited[y][x] || !is_member(x, y) {
+                    continue;
+                }
+
+                let mut region = Vec::new();
+                let mut queue = VecDeque::from([(x, y)]);
+                visited[y][x] = true;
+
+                while let Some((x, y)) = queue.pop_front() {
+                    region.push((x, y));
+
+                    for (x, y) in self.surrounding_cells_coords(x, y) {
+                        if !visited[y][x] && is_member(x, y) {
+                            visited[y][x] = true;
+                            queue.push_back((x, y));
+                        }
+                    }
+                }
+
+                result.push(region);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::board;
+    use crate::{Adjacency, Difficulty, ExposeResult, FieldSize, GameConfig, WinCondition};
+
+    #[test]
+    fn threebv_counts_openings_and_isolated_numbers() {
+        let mut game = board(&["*.*..", "***..", "....."]);
+        assert_eq!(game.threebv(), 5);
+
+        game.try_expose_cell(4, 0);
+        for (x, y) in [(1, 0), (0, 2), (1, 2)] {
+            game.try_expose_cell(x, y);
+        }
+        assert_eq!(game.threebv_per_second(Duration::from_millis(2500)), None);
+
+        game.try_expose_cell(2, 2);
+        assert_eq!(game.threebv_per_second(Duration::from_millis(2500)), Some(2.0));
+        assert_eq!(game.threebv_per_second(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn mirrored_layouts_score_full_symmetry() {
+        let score = board(&["*..*", "2..2", "...."]).symmetry_score();
+
+        assert_eq!(score.horizontal, 1.0);
+        assert_eq!(score.vertical, 8.0 / 12.0);
+        assert_eq!(score.rotational, 8.0 / 12.0);
+    }
+
+    #[test]
+    fn heavy_cells_only_mirror_cells_of_the_same_weight() {
+        let score = board(&["*..2"]).symmetry_score();
+
+        assert_eq!(score.horizontal, 0.5);
+        assert_eq!(score.vertical, 1.0);
+        assert_eq!(score.rotational, 0.5);
+    }
+
+    #[test]
+    fn an_empty_board_is_fully_symmetric() {
+        let config = GameConfig::new(FieldSize::Custom { width: 0, height: 0 }, Difficulty::Exact { gophers: 0 });
+        let score = GopherSweeper::new(config).symmetry_score();
+
+        assert_eq!(score, SymmetryScore { horizontal: 1.0, vertical: 1.0, rotational: 1.0 });
+    }
+
+    #[test]
+    fn gopher_clusters_follow_the_configured_adjacency() {
+        let mut game = board(&["*..*", ".2.*", "...."]);
+        assert_eq!(game.gopher_clusters(), vec![vec![(0, 0), (1, 1)], vec![(3, 0), (3, 1)]]);
+
+        game.config.adjacency = Adjacency::Diagonal;
+        assert_eq!(game.gopher_clusters(), vec![vec![(0, 0), (1, 1)], vec![(3, 0)], vec![(3, 1)]]);
+    }
+
+    #[test]
+    fn openness_is_high_on_open_boards_and_low_on_dense_ones() {
+        assert_eq!(board(&["...", "..."]).openness(), 1.0);
+        assert_eq!(board(&["*.*", "...", "..."]).openness(), 6.0 / 7.0);
+        assert_eq!(board(&["*.*", ".*.", "*.*"]).openness(), 0.0);
+        assert_eq!(board(&["**", "**"]).openness(), 0.0);
+    }
+
+    #[test]
+    fn cells_are_grouped_by_their_number() {
+        let groups = board(&["*..", "..2"]).cells_by_number();
+
+        assert_eq!(groups[0], vec![(0, 0), (2, 1)]);
+        assert_eq!(groups[1], vec![(0, 1)]);
+        assert_eq!(groups[2], vec![(2, 0)]);
+        assert_eq!(groups[3], vec![(1, 0), (1, 1)]);
+        assert!(groups[4..].iter().all(Vec::is_empty));
+
+        let crowded = board(&["222", "2.2", "222"]).cells_by_number();
+        assert_eq!(crowded[8], vec![(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)]);
+        assert_eq!(crowded[4].len(), 4);
+    }
+
+    #[test]
+    fn the_number_histogram_totals_the_cell_count() {
+        assert_eq!(board(&["*..", "..2"]).number_histogram(), [2, 1, 1, 2, 0, 0, 0, 0, 0]);
+
+        let crowded = board(&["222", "2.2", "222"]).number_histogram();
+        assert_eq!(crowded, [0, 0, 0, 0, 4, 0, 0, 0, 5]);
+        assert_eq!(crowded.iter().sum::<usize>(), 9);
+    }
+
+    #[test]
+    fn optimal_clicks_take_threebv_clicks() {
+        let game = board(&["*..*", "....", "...*"]);
+        let clicks = game.optimal_clicks().unwrap();
+        assert_eq!(clicks.len(), game.threebv());
+        assert_eq!(clicks[0], (0, 2));
+
+        let mut replay = game.clone();
+        let results: Vec<ExposeResult> = clicks.iter().map(|&(x, y)| replay.try_expose_cell(x, y)).collect();
+        assert_eq!(results.last(), Some(&ExposeResult::Win));
+        assert!(!results.contains(&ExposeResult::WasAlreadyExposed));
+
+        assert_eq!(board(&["*.", "..", ".."]).optimal_clicks(), None);
+    }
+
+    #[test]
+    fn hidden_regions_expect_their_remaining_gophers() {
+        let mut game = board(&["*..*.", "....."]);
+        game.try_expose_cell(2, 0);
+        game.try_expose_cell(2, 1);
+
+        let expectations = game.region_gopher_expectations();
+        assert_eq!(expectations.len(), 2);
+        assert_eq!(expectations[0].0, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(expectations[1].0, vec![(3, 0), (4, 0), (3, 1), (4, 1)]);
+        assert!(expectations.iter().all(|(_, expected)| (expected - 1.0).abs() < 1e-5));
+
+        game.toggle_flag(4, 0);
+        let expectations = game.region_gopher_expectations();
+        assert_eq!(expectations[1].0, vec![(3, 0), (3, 1), (4, 1)]);
+        assert!(expectations.iter().all(|(_, expected)| (expected - 0.5).abs() < 1e-5));
+    }
+
+    #[test]
+    fn difficulty_scores_are_clicks_per_safe_cell() {
+        assert_eq!(board(&["*..", "...", "..."]).difficulty_score(), 0.125);
+        assert_eq!(board(&[".*.", "..."]).difficulty_score(), 1.0);
+        assert_eq!(board(&["**", "**"]).difficulty_score(), 0.0);
+    }
+
+    #[test]
+    fn threebv_per_second_needs_a_won_game() {
+        let mut game = board(&["*..", "...", "..."]);
+        game.config = game.config.clone().with_win_condition(WinCondition::FlagAllGophers);
+        game.try_expose_cell(2, 2);
+        assert_eq!(game.remaining_cells, 0);
+        assert_eq!(game.threebv_per_second(Duration::from_secs(1)), None);
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.threebv_per_second(Duration::from_secs(1)), Some(1.0));
+
+        let practice = GopherSweeper::new_practice(GameConfig::new(FieldSize::Small, Difficulty::Easy), 0);
+        assert_eq!(practice.threebv_per_second(Duration::from_secs(1)), None);
+    }
+}
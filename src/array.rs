@@ -0,0 +1,36 @@
+use crate::GopherSweeper;
+use ndarray::Array2;
+
+impl GopherSweeper {
+    /// The gopher count of every cell, shaped `(height, width)` and indexed `[[y, x]]`.
+    pub fn gopher_array(&self) -> Array2<u8> {
+        self.array_of(|x, y| self.field[y][x].gopher_count)
+    }
+
+    /// The number of surrounding gophers of every cell, gophers included, shaped
+    /// `(height, width)` and indexed `[[y, x]]`.
+    pub fn number_array(&self) -> Array2<u8> {
+        self.array_of(|x, y| self.field[y][x].surrounding_gophers)
+    }
+
+    fn array_of(&self, value: impl Fn(usize, usize) -> u8) -> Array2<u8> {
+        let (width, height) = self.config.size();
+        Array2::from_shape_fn((height, width), |(y, x)| value(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::board;
+    use ndarray::array;
+
+    #[test]
+    fn arrays_are_shaped_height_by_width() {
+        let game = board(&["*..", "..2"]);
+
+        assert_eq!(game.gopher_array().dim(), (2, 3));
+        assert_eq!(game.gopher_array(), array![[1, 0, 0], [0, 0, 2]]);
+        assert_eq!(game.number_array(), array![[0, 3, 2], [1, 3, 0]]);
+        assert_eq!(game.number_array()[[0, 2]], game.field[0][2].surrounding_gophers);
+    }
+}
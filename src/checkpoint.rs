@@ -0,0 +1,160 @@
+use crate::{CellEventKind, GopherSweeper, LossReason};
+use std::time::Instant;
+
+/// The player-visible state of a game at some point: which cells are exposed
+/// and flagged (one bit each) plus the counters and outcome. Much smaller than
+/// a clone, since layout, notes and config are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    exposed: Vec<u64>,
+    flagged: Vec<u64>,
+    remaining_cells: usize,
+    exposed_cells: usize,
+    started: bool,
+    won: bool,
+    loss: Option<LossReason>,
+    started_at: Option<Instant>,
+}
+
+impl GopherSweeper {
+    pub fn checkpoint(&self) -> Checkpoint {
+        let cells = self.field.iter().flatten().count();
+        let mut exposed = vec![0; cells.div_ceil(64)];
+        let mut flagged = vec![0; cells.div_ceil(64)];
+
+        for (i, cell) in self.field.iter().flatten().enumerate() {
+            exposed[i / 64] |= (cell.is_exposed as u64) << (i % 64);
+            flagged[i / 64] |= (cell.is_flagged as u64) << (i % 64);
+        }
+
+        Checkpoint {
+            exposed,
+            flagged,
+            remaining_cells: self.remaining_cells,
+            exposed_cells: self.exposed_cells,
+            started: self.started,
+            won: self.won,
+            loss: self.loss,
+            started_at: self.started_at,
+        }
+    }
+
+    /// Rolls exposure, flags and the game outcome back to `checkpoint`, which must
+    /// come from this game. A layout generated since is kept. The observer hears
+    /// about every cell whose exposure or flag changes.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) {
+        self.clear_transients();
+
+        let (width, _) = self.config.size();
+        let mut events = Vec::new();
+
+        for (i, cell) in self.field.iter_mut().flatten().enumerate() {
+            let is_exposed = checkpoint.exposed[i / 64] >> (i % 64) & 1 == 1;
+            let is_flagged = checkpoint.flagged[i / 64] >> (i % 64) & 1 == 1;
+
+            if cell.is_exposed != is_exposed {
+                let kind = if is_exposed { CellEventKind::Exposed } else { CellEventKind::Unexposed };
+                events.push((i % width, i / width, kind));
+            }
+
+            if cell.is_flagged != is_flagged {
+                let kind = if is_flagged { CellEventKind::Flagged } else { CellEventKind::Unflagged };
+                events.push((i % width, i / width, kind));
+            }
+
+            cell.is_exposed = is_exposed;
+            cell.is_flagged = is_flagged;
+        }
+
+        for (x, y, kind) in events {
+            self.notify(x, y, kind);
+        }
+
+        self.remaining_cells = checkpoint.remaining_cells;
+        self.exposed_cells = checkpoint.exposed_cells;
+        self.started = checkpoint.started;
+        self.won = checkpoint.won;
+        self.loss = checkpoint.loss;
+        self.started_at = checkpoint.started_at;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::board;
+    use crate::{Cell, CellEvent, Difficulty, ExposeResult, FieldSize, GameConfig, GameState, RevealPolicy};
+    use std::sync::{Arc, Mutex};
+
+    fn same_cells(a: &GopherSweeper, b: &GopherSweeper) -> bool {
+        a.field.iter().flatten().zip(b.field.iter().flatten()).all(|(a, b)| a == b)
+    }
+
+    #[test]
+    fn restore_undoes_an_expose_and_a_flag() {
+        let mut game = GopherSweeper::new_seeded(GameConfig::new(FieldSize::Small, Difficulty::Easy), 3);
+        let gophers = game.cells_where(Cell::has_gopher);
+        game.toggle_flag(gophers[0].0, gophers[0].1);
+        let before = game.clone();
+        let checkpoint = game.checkpoint();
+
+        let (x, y) = *gophers.last().unwrap();
+        assert!(y * 10 + x >= 64);
+        game.toggle_flag(x, y);
+        game.toggle_flag(gophers[0].0, gophers[0].1);
+        let (x, y) = game.cells_where(|cell| !cell.has_gopher() && cell.surrounding_gophers == 0)[0];
+        game.try_expose_cell(x, y);
+        game.restore(&checkpoint);
+
+        assert!(same_cells(&game, &before));
+        assert_eq!(game.remaining_cells, before.remaining_cells);
+        assert_eq!(game.checkpoint(), checkpoint);
+    }
+
+    #[test]
+    fn restore_undoes_a_revealed_loss() {
+        let mut game = board(&["*..", "..2"]);
+        game.config = game.config.clone().with_reveal_on_loss(RevealPolicy::Reveal);
+        game.try_expose_cell(1, 0);
+        game.clear_transients();
+        let before = game.clone();
+        let checkpoint = game.checkpoint();
+
+        assert_eq!(game.try_expose_cell(0, 0), ExposeResult::HasGopher);
+        assert!(game.cell(2, 1).is_exposed);
+        game.restore(&checkpoint);
+
+        assert_eq!(game.state(), GameState::Playing);
+        assert!(same_cells(&game, &before));
+        assert_eq!(game.try_expose_cell(1, 1), ExposeResult::Exposed);
+    }
+
+    #[test]
+    fn restore_notifies_every_changed_cell() {
+        let mut game = board(&["*..", "..2"]);
+        game.config = game.config.clone().with_reveal_on_loss(RevealPolicy::Reveal);
+        game.toggle_flag(2, 0);
+        let checkpoint = game.checkpoint();
+
+        game.toggle_flag(2, 0);
+        game.toggle_flag(1, 1);
+        game.toggle_flag(1, 1);
+        game.try_expose_cell(2, 0);
+        game.try_expose_cell(0, 0);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        game.set_observer(Box::new(move |event: CellEvent| sink.lock().unwrap().push((event.x, event.y, event.kind))));
+        game.restore(&checkpoint);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (0, 0, CellEventKind::Unexposed),
+                (2, 0, CellEventKind::Unexposed),
+                (2, 0, CellEventKind::Flagged),
+                (2, 1, CellEventKind::Unexposed),
+            ],
+        );
+    }
+}
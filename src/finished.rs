@@ -0,0 +1,106 @@
+use crate::{BoardSnapshot, Cell, GameConfig, GameState, GopherSweeper};
+
+/// A game that can no longer change, for keeping the final board around after
+/// it ends. Only queries are available; there is no way back to a mutable game.
+///
+/// ```compile_fail
+/// # use gophersweeper_lib::GopherSweeper;
+/// let mut finished = GopherSweeper::from_rle("3x1:*").unwrap().into_finished();
+/// finished.try_expose_cell(1, 0);
+/// ```
+#[derive(Clone)]
+pub struct FinishedGame(GopherSweeper);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameSummary {
+    pub won: bool,
+    pub remaining_cells: usize,
+    pub total_safe_cells: usize,
+    pub flags_placed: usize,
+    pub correct_flags: usize,
+    pub threebv: usize,
+}
+
+impl GopherSweeper {
+    pub fn into_finished(self) -> FinishedGame {
+        FinishedGame(self)
+    }
+}
+
+impl FinishedGame {
+    pub fn config(&self) -> &GameConfig {
+        &self.0.config
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> &Cell {
+        self.0.cell(x, y)
+    }
+
+    pub fn snapshot(&self) -> BoardSnapshot {
+        self.0.snapshot()
+    }
+
+    pub fn summary(&self) -> GameSummary {
+        GameSummary {
+            won: self.0.state() == GameState::Won,
+            remaining_cells: self.0.remaining_cells,
+            total_safe_cells: self.0.total_safe_cells(),
+            flags_placed: self.0.flags_placed(),
+            correct_flags: self.0.correct_flags(),
+            threebv: self.0.threebv(),
+        }
+    }
+
+    /// Every cell holding a gopher, in row-major order.
+    pub fn gopher_positions(&self) -> Vec<(usize, usize)> {
+        self.0.cells_where(Cell::has_gopher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::board;
+    use crate::{ExposeResult, RevealPolicy, VisibleCell};
+
+    #[test]
+    fn finished_games_keep_the_final_state() {
+        let mut game = board(&["*..", "...", "..."]);
+        game.toggle_flag(0, 0);
+        assert_eq!(game.try_expose_cell(2, 2), ExposeResult::Win);
+
+        let finished = game.into_finished();
+
+        assert_eq!(finished.summary(), GameSummary {
+            won: true,
+            remaining_cells: 0,
+            total_safe_cells: 8,
+            flags_placed: 1,
+            correct_flags: 1,
+            threebv: 1,
+        });
+        assert_eq!(finished.snapshot().cells[0], vec![VisibleCell::Flagged, VisibleCell::Exposed(1), VisibleCell::Exposed(0)]);
+    }
+
+    #[test]
+    fn lost_games_keep_their_wrong_flags() {
+        let mut game = board(&["*..", "..2"]);
+        game.config.reveal_on_loss = RevealPolicy::Reveal;
+        game.toggle_flag(1, 0);
+        assert_eq!(game.try_expose_cell(0, 0), ExposeResult::HasGopher);
+
+        let finished = game.into_finished();
+
+        assert_eq!(finished.summary(), GameSummary {
+            won: false,
+            remaining_cells: 4,
+            total_safe_cells: 4,
+            flags_placed: 1,
+            correct_flags: 0,
+            threebv: 4,
+        });
+        assert_eq!(finished.gopher_positions(), vec![(0, 0), (2, 1)]);
+        assert_eq!(finished.snapshot().cell(2, 1), VisibleCell::Gopher);
+        assert!(finished.cell(1, 0).is_flagged);
+    }
+}
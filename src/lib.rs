@@ -1,5 +1,36 @@
-use rand::Rng;
-use std::collections::HashSet;
+mod analysis;
+#[cfg(feature = "ndarray")]
+mod array;
+mod checkpoint;
+mod finished;
+mod pattern;
+mod retry;
+mod rle;
+mod shared;
+mod solver;
+#[cfg(feature = "svg")]
+mod svg;
+mod tiles;
+mod transform;
+
+pub use analysis::SymmetryScore;
+pub use checkpoint::Checkpoint;
+pub use finished::{FinishedGame, GameSummary};
+pub use pattern::{Pattern, PatternError};
+pub use rle::RleError;
+pub use shared::SharedGame;
+pub use solver::{CertainMoves, Constraint, ExplainedMove, Frontier, MoveKind};
+pub use tiles::TileGenerator;
+
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 const SMALL:  (usize, usize) = (10, 8);
 const MEDIUM: (usize, usize) = (18, 12);
@@ -9,198 +40,1855 @@ const EASY:   f32 = 0.1;
 const NORMAL: f32 = 0.15;
 const HARD:   f32 = 0.2;
 
+const DEFAULT_MAX_CELLS: usize = 1 << 24;
+const MAX_GENERATION_ATTEMPTS: usize = 100;
+const MAX_REJECTED_DRAWS_PER_CELL: usize = 16;
+
+const CAMPAIGN_FINAL_DENSITY: f32 = 0.25;
+const CAMPAIGN_FINAL_SCALE:   f32 = 1.5;
+
+const NEIGHBOR_OFFSETS: [(i8, i8); 8] = [(-1, 0), (0, -1), (1, 0), (0, 1), (-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+#[derive(Clone)]
 pub struct GopherSweeper {
     pub config: GameConfig,
-    field: Vec<Vec<Cell>>,
+    field: Field,
     remaining_cells: usize,
+    exposed_cells: usize,
+    total_safe_cells: usize,
+    generated: bool,
+    started: bool,
+    seed: Option<u64>,
+    transients: Vec<(usize, usize)>,
+    observer: Observer,
+    won: bool,
+    loss: Option<LossReason>,
+    started_at: Option<Instant>,
 }
 
 impl GopherSweeper {
+    /// Configs asking for more gophers than the board can hold get as many as
+    /// fit; `try_new` rejects them instead.
     pub fn new(config: GameConfig) -> Self {
+        let mut result = GopherSweeper::empty(config, None);
+
+        if result.config.safe_radius().is_none() {
+            result.generate(None);
+        }
+
+        result
+    }
+
+    /// Like `new`, but rejects configs the rest of the crate would panic on or
+    /// struggle to allocate, such as boards larger than `GameConfig::max_cells`
+    /// or more gophers than the cells can hold.
+    pub fn try_new(config: GameConfig) -> Result<Self, ConfigError> {
+        GopherSweeper::try_generate(config, None)
+    }
+
+    pub fn try_new_seeded(config: GameConfig, seed: u64) -> Result<Self, ConfigError> {
+        GopherSweeper::try_generate(config, Some(seed))
+    }
+
+    /// Like `try_new` (or `try_new_seeded` when `seed` is given), but checks
+    /// `cancel` between generation attempts and returns `ConfigError::Cancelled`
+    /// once another thread sets it.
+    pub fn try_new_cancellable(config: GameConfig, seed: Option<u64>, cancel: Arc<AtomicBool>) -> Result<Self, ConfigError> {
+        GopherSweeper::try_generate_with(config, seed, Some(&cancel))
+    }
+
+    fn try_generate(config: GameConfig, seed: Option<u64>) -> Result<Self, ConfigError> {
+        GopherSweeper::try_generate_with(config, seed, None)
+    }
+
+    fn try_generate_with(config: GameConfig, seed: Option<u64>, cancel: Option<&AtomicBool>) -> Result<Self, ConfigError> {
+        config.validate()?;
+
+        let mut result = GopherSweeper::empty(config, seed);
+
+        if result.config.safe_radius().is_none() {
+            result.generate_with(None, cancel)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Seeded boards are generated with `ChaCha8Rng::seed_from_u64(seed)` from
+    /// `rand_chacha` 0.3, drawing each candidate cell as x then y by widening
+    /// multiplication of a `u64` draw, so the same seed and config give the same
+    /// layout across crate versions.
+    pub fn new_seeded(config: GameConfig, seed: u64) -> Self {
+        let mut result = GopherSweeper::empty(config, Some(seed));
+
+        if result.config.safe_radius().is_none() {
+            result.generate(None);
+        }
+
+        result
+    }
+
+    /// Creates the board of the day, seeded with the 64-bit FNV-1a hash of the
+    /// zero-padded ASCII date `YYYY-MM-DD` (e.g. `2024-03-09`) and otherwise
+    /// generated exactly like `new_seeded`.
+    pub fn daily(config: GameConfig, year: u16, month: u8, day: u8) -> Self {
+        let date = format!("{year:04}-{month:02}-{day:02}");
+
+        GopherSweeper::new_from_key(config, date.as_bytes())
+    }
+
+    /// Creates a board from arbitrary bytes such as a room code, seeded with their
+    /// 64-bit FNV-1a hash and otherwise generated exactly like `new_seeded`.
+    pub fn new_from_key(config: GameConfig, key: &[u8]) -> Self {
+        GopherSweeper::new_seeded(config, fnv1a(key))
+    }
+
+    /// One `new_seeded` board per seed, in order. With the `rayon` feature the
+    /// boards are generated in parallel.
+    pub fn generate_batch(config: &GameConfig, seeds: &[u64]) -> Vec<Self> {
+        let generate = |&seed: &u64| GopherSweeper::new_seeded(config.clone(), seed);
+
+        #[cfg(feature = "rayon")]
+        return seeds.par_iter().map(generate).collect();
+
+        #[cfg(not(feature = "rayon"))]
+        seeds.iter().map(generate).collect()
+    }
+
+    /// Two `new_seeded` boards with different layouts whose `difficulty_score`s
+    /// are at most `tolerance` apart, with both seeds drawn from `seed`. If no
+    /// such partner turns up within a bounded number of candidates, the closest
+    /// one is used. Deferred layouts can't be rated, so any partner fits.
+    pub fn fair_pair(config: &GameConfig, seed: u64, tolerance: f32) -> (Self, Self) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let first = GopherSweeper::new_seeded(config.clone(), rng.gen());
+        let mut closest: Option<(f32, GopherSweeper)> = None;
+
+        for _ in 0..MAX_GENERATION_ATTEMPTS {
+            let candidate = GopherSweeper::new_seeded(config.clone(), rng.gen());
+
+            if first.generated && candidate.same_layout(&first) {
+                continue;
+            }
+
+            let distance = (candidate.difficulty_score() - first.difficulty_score()).abs();
+
+            if distance <= tolerance {
+                return (first, candidate);
+            }
+
+            if closest.as_ref().is_none_or(|(best, _)| distance < *best) {
+                closest = Some((distance, candidate));
+            }
+        }
+
+        let second = match closest {
+            Some((_, candidate)) => candidate,
+            None => GopherSweeper::new_seeded(config.clone(), rng.gen()),
+        };
+
+        (first, second)
+    }
+
+    /// Creates a seeded board with every safe cell already exposed, so only flagging
+    /// and chording are left to practice. Exposing one of the remaining cells still
+    /// returns `HasGopher`, and since nothing safe is left to expose, exposing never
+    /// returns `Win`; the board is only won by flagging under
+    /// `WinCondition::FlagAllGophers`. Any safe radius is ignored because the layout
+    /// is generated upfront.
+    pub fn new_practice(config: GameConfig, seed: u64) -> Self {
+        let mut result = GopherSweeper::empty(config, Some(seed));
+
+        result.generate(None);
+
+        for cell in result.field.iter_mut().flatten() {
+            if !cell.has_gopher() {
+                cell.is_exposed = true;
+            }
+        }
+
+        result.remaining_cells = 0;
+        result.exposed_cells = result.total_safe_cells;
+        result.started = true;
+        result
+    }
+
+    fn empty(config: GameConfig, seed: Option<u64>) -> Self {
         let (width, height) = config.size();
         let gophers = config.gophers();
 
-        let mut result = GopherSweeper {
+        let safe_cells = (width * height).saturating_sub(gophers);
+
+        GopherSweeper {
             config,
-            remaining_cells: width * height - gophers,
-            field: Vec::with_capacity(height),
+            remaining_cells: safe_cells,
+            exposed_cells: 0,
+            total_safe_cells: safe_cells,
+            field: Field::new(width, height),
+            generated: false,
+            started: false,
+            seed,
+            transients: Vec::new(),
+            observer: Observer(None),
+            won: false,
+            loss: None,
+            started_at: None,
+        }
+    }
+
+    /// Returns whether the layout meets every constraint of the config. Otherwise
+    /// the last attempt is kept anyway.
+    fn generate(&mut self, safe_center: Option<(usize, usize)>) -> bool {
+        self.generate_with(safe_center, None).is_ok()
+    }
+
+    fn generate_with(&mut self, safe_center: Option<(usize, usize)>, cancel: Option<&AtomicBool>) -> Result<(), ConfigError> {
+        match self.seed {
+            Some(seed) => self.plant_gophers(&mut ChaCha8Rng::seed_from_u64(seed), safe_center, cancel),
+            None => self.plant_gophers(&mut rand::thread_rng(), safe_center, cancel),
+        }
+    }
+
+    fn plant_gophers<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        safe_center: Option<(usize, usize)>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<(), ConfigError> {
+        let (width, height) = self.config.size();
+        let max_per_cell = self.config.max_gophers_per_cell();
+        let capacity = |cells: usize| cells * max_per_cell as usize;
+
+        let margin = self.config.gopher_margin();
+        let interior = width.saturating_sub(2 * margin) * height.saturating_sub(2 * margin);
+        let gophers = self.config.gophers().min(capacity(interior));
+
+        let in_margin = |x: usize, y: usize| x < margin || y < margin || x + margin >= width || y + margin >= height;
+        let in_zone = |(x, y, radius): (usize, usize, usize), cx: usize, cy: usize| {
+            cx.abs_diff(x) <= radius && cy.abs_diff(y) <= radius
         };
 
-        for y in 0..height {
-            result.field.push(Vec::with_capacity(width));
+        let safe_zone = safe_center.and_then(|(x, y)| {
+            let mut radius = self.config.safe_radius().unwrap_or(0);
+
+            while capacity(interior - zone_area(x, y, radius, margin, width, height)) < gophers {
+                if radius == 0 { return None }
+                radius -= 1;
+            }
+
+            Some((x, y, radius))
+        });
+
+        let max_number = self.config.max_cell_number().unwrap_or(u8::MAX);
+        self.generated = true;
+
+        for _ in 0..MAX_GENERATION_ATTEMPTS {
+            if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+                return Err(ConfigError::Cancelled);
+            }
 
-            for _ in 0..width {
-                result.field[y].push(Cell::default());
+            for cell in self.field.iter_mut().flatten() {
+                cell.gopher_count = 0;
+                cell.kind = CellKind::Normal;
+                cell.variant = 0;
+            }
+
+            let placed = self.scatter_gophers(rng, gophers, |cx, cy| {
+                in_margin(cx, cy) || safe_zone.is_some_and(|zone| in_zone(zone, cx, cy))
+            });
+
+            self.scatter_anti_gophers(rng);
+            self.recompute_counts();
+
+            if placed && self.field.iter().flatten().all(|cell| cell.has_gopher() || cell.surrounding_gophers <= max_number) {
+                self.assign_variants(rng);
+                return Ok(());
             }
         }
 
-        let mut rng = rand::thread_rng();
+        Err(ConfigError::GenerationFailed { attempts: MAX_GENERATION_ATTEMPTS })
+    }
+
+    /// Gives up and returns false when a `max_cell_number` cap leaves no room for
+    /// the next gopher after many draws.
+    fn scatter_gophers<R: Rng>(&mut self, rng: &mut R, gophers: usize, excluded: impl Fn(usize, usize) -> bool) -> bool {
+        let (width, height) = self.config.size();
+        let max_per_cell = self.config.max_gophers_per_cell();
+        let max_number = self.config.max_cell_number();
+
         let mut random_coords: (usize, usize);
-        let mut planted_gophers = HashSet::with_capacity(gophers);
+        let mut planted_gophers = 0;
+        let mut rejected_draws = 0;
+
+        while planted_gophers < gophers {
+            random_coords = (random_below(rng, width), random_below(rng, height));
 
-        while planted_gophers.len() < gophers {
-            random_coords = (rng.gen_range(0..width), rng.gen_range(0..height));
+            if excluded(random_coords.0, random_coords.1) {
+                continue;
+            }
 
-            if planted_gophers.insert(random_coords) {
-                result.field[random_coords.1][random_coords.0].has_gopher = true;
+            if let Some(max) = max_number {
+                if !self.gopher_fits_under(random_coords.0, random_coords.1, max) {
+                    rejected_draws += 1;
 
-                for (x, y) in result.surrounding_cells_coords(random_coords.0, random_coords.1) {
-                    result.field[y][x].surrounding_gophers += 1;
+                    if rejected_draws > MAX_REJECTED_DRAWS_PER_CELL * width * height { return false }
+                    continue;
                 }
             }
+
+            let cell = &mut self.field[random_coords.1][random_coords.0];
+
+            if cell.gopher_count < max_per_cell {
+                cell.gopher_count += 1;
+                planted_gophers += 1;
+            }
         }
 
-        result
+        true
     }
 
-    pub fn cell(&self, x: usize, y: usize) -> &Cell {
-        &self.field[y][x]
-    }
+    fn gopher_fits_under(&self, x: usize, y: usize, max_number: u8) -> bool {
+        self.surrounding_cells_coords(x, y)
+            .into_iter()
+            .filter(|&(x, y)| !self.field[y][x].has_gopher())
+            .all(|(x, y)| {
+                let number = self.surrounding_cells_coords(x, y)
+                    .into_iter()
+                    .map(|(x, y)| self.field[y][x].gopher_count as usize)
+                    .sum::<usize>();
 
-    pub fn toggle_flag(&mut self, x: usize, y: usize) {
-        let mut cell = &mut self.field[y][x];
-        cell.is_flagged = !cell.is_flagged;
+                number < max_number as usize
+            })
     }
 
-    pub fn try_expose_cell(&mut self, x: usize, y: usize) -> ExposeResult {
-        let cell = &self.field[y][x];
+    fn assign_variants<R: Rng>(&mut self, rng: &mut R) {
+        let weights = self.config.gopher_variant_weights();
+        let total: u64 = weights.iter().map(|&weight| weight as u64).sum();
 
-        if cell.is_exposed { return ExposeResult::WasAlreadyExposed }
-        if cell.is_flagged { return ExposeResult::IsFlagged }
-        if cell.has_gopher { return ExposeResult::HasGopher }
+        if weights.len() < 2 || total == 0 { return }
 
-        self.expose_recursively(x, y);
+        for cell in self.field.iter_mut().flatten().filter(|cell| cell.has_gopher()) {
+            let mut draw = random_below(rng, total as usize) as u64;
 
-        if self.remaining_cells == 0 { return ExposeResult::Win }
+            for (variant, &weight) in weights.iter().enumerate() {
+                if draw < weight as u64 {
+                    cell.variant = variant as u8;
+                    break;
+                }
 
-        ExposeResult::Exposed
+                draw -= weight as u64;
+            }
+        }
     }
 
-    fn expose_recursively(&mut self, x: usize, y: usize) {
-        let mut cell = &mut self.field[y][x];
+    fn scatter_anti_gophers<R: Rng>(&mut self, rng: &mut R) {
+        let (width, height) = self.config.size();
+        let free_cells = self.field.iter().flatten().filter(|cell| !cell.has_gopher()).count();
+        let mut remaining = self.config.anti_gophers().min(free_cells);
 
-        cell.is_exposed = true;
-        self.remaining_cells -= 1;
+        while remaining > 0 {
+            let (x, y) = (random_below(rng, width), random_below(rng, height));
+            let cell = &mut self.field[y][x];
 
-        if cell.surrounding_gophers == 0 {
-            for (x, y) in self.surrounding_cells_coords(x, y) {
-                if !self.field[y][x].is_exposed {
-                    self.expose_recursively(x, y);
-                }
+            if !cell.has_gopher() && cell.kind == CellKind::Normal {
+                cell.kind = CellKind::AntiGopher;
+                remaining -= 1;
             }
         }
     }
 
-    fn surrounding_cells_coords(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let mut result: Vec<(usize, usize)> = Vec::with_capacity(8);
+    /// Recomputes every cell's `surrounding_gophers` plus the remaining, total safe
+    /// and exposed cell counts from the current `gopher_count`/`is_exposed` state,
+    /// for use after editing cells by hand.
+    pub fn recompute_counts(&mut self) {
+        let (width, height) = self.config.size();
 
-        let (w, h) = self.config.size();
+        for y in 0..height {
+            for x in 0..width {
+                let neighbors = self.surrounding_cells_coords(x, y);
+                let gophers = neighbors.iter().fold(0u8, |sum, &(x, y)| sum.saturating_add(self.field[y][x].gopher_count));
+                let anti_gophers = neighbors.iter().filter(|&&(x, y)| self.field[y][x].kind == CellKind::AntiGopher).count();
 
-        if x > 0 {
-            result.push((x - 1, y));
+                self.field[y][x].surrounding_gophers = gophers.saturating_sub(anti_gophers as u8);
+            }
         }
-        
-        if y > 0 {
-            result.push((x, y - 1));
+
+        let safe_cells = self.field.iter().flatten().filter(|cell| !cell.has_gopher());
+
+        self.total_safe_cells = safe_cells.clone().count();
+        self.remaining_cells = safe_cells.filter(|cell| !cell.is_exposed).count();
+        self.exposed_cells = self.field.iter().flatten().filter(|cell| cell.is_exposed).count();
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> &Cell {
+        &self.field[y][x]
+    }
+
+    /// Replaces the note on a cell; an empty note removes it.
+    pub fn set_note(&mut self, x: usize, y: usize, note: CellNote) {
+        self.field[y][x].note = (note != CellNote::default()).then(|| Box::new(note));
+    }
+
+    pub fn note(&self, x: usize, y: usize) -> &CellNote {
+        self.field[y][x].note.as_deref().unwrap_or(&EMPTY_NOTE)
+    }
+
+    /// Protected cells can't be exposed: `try_expose_cell` and friends return
+    /// `ExposeResult::Protected`, and cascades and chords pass them by. Reveal
+    /// policies still expose them.
+    pub fn set_protected(&mut self, x: usize, y: usize, protected: bool) {
+        self.field[y][x].is_protected = protected;
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        let (width, height) = self.config.size();
+        x < width && y < height
+    }
+
+    pub fn try_cell(&self, x: usize, y: usize) -> Option<&Cell> {
+        self.in_bounds(x, y).then(|| &self.field[y][x])
+    }
+
+    pub fn surrounding_gophers(&self, x: usize, y: usize) -> Option<u8> {
+        self.try_cell(x, y).map(|cell| cell.surrounding_gophers)
+    }
+
+    /// The variant of the gopher at `(x, y)`, or `None` without one.
+    pub fn gopher_variant(&self, x: usize, y: usize) -> Option<u8> {
+        self.try_cell(x, y).filter(|cell| cell.has_gopher()).map(|cell| cell.variant)
+    }
+
+    pub fn total_safe_cells(&self) -> usize {
+        self.total_safe_cells
+    }
+
+    /// How many cells are exposed, including gophers exposed by a reveal policy.
+    pub fn exposed_count(&self) -> usize {
+        self.exposed_cells
+    }
+
+    pub fn toggle_flag(&mut self, x: usize, y: usize) -> ToggleFlagResult {
+        self.clear_transients();
+
+        if self.state() != GameState::Playing { return ToggleFlagResult::GameOver }
+
+        let cell = &mut self.field[y][x];
+
+        if cell.is_exposed {
+            if self.config.exposed_flag_chords() {
+                if let Some(result) = self.chord(x, y) { return ToggleFlagResult::Chorded(result) }
+            }
+
+            return ToggleFlagResult::CellWasExposed;
         }
-        
-        if x + 1 < w {
-            result.push((x + 1, y));
+        if !self.started && self.config.flag_after_first_expose() { return ToggleFlagResult::GameNotStarted }
+
+        cell.is_flagged = !cell.is_flagged;
+        let is_flagged = cell.is_flagged;
+
+        self.notify(x, y, if is_flagged { CellEventKind::Flagged } else { CellEventKind::Unflagged });
+
+        if self.config.win_condition() == WinCondition::FlagAllGophers && self.flags_match_gophers() {
+            self.won = true;
+            self.apply_reveal_policy(self.config.reveal_on_win());
+            return ToggleFlagResult::Win;
         }
-        
-        if y + 1 < h {
-            result.push((x, y + 1));
+
+        if is_flagged {
+            if self.config.assist_on_correct_flag() && self.field[y][x].has_gopher() {
+                for (nx, ny) in self.surrounding_cells_coords(x, y) {
+                    if !self.chord_would_lose(nx, ny) && self.chord(nx, ny) == Some(ExposeResult::Win) {
+                        return ToggleFlagResult::Win;
+                    }
+                }
+            }
+
+            ToggleFlagResult::Flagged
+        } else {
+            ToggleFlagResult::Unflagged
         }
-        
-        if x > 0 && y > 0 {
-            result.push((x - 1, y - 1));
+    }
+
+    /// Removes every flag, leaving exposed cells and the layout as they are.
+    pub fn reset_flags(&mut self) {
+        self.clear_transients();
+
+        for (x, y) in self.cells_where(|cell| cell.is_flagged) {
+            self.field[y][x].is_flagged = false;
+            self.notify(x, y, CellEventKind::Unflagged);
         }
-        
-        if x > 0 && y + 1 < h {
-            result.push((x - 1, y + 1));
+    }
+
+    /// How many cells `try_expose_cell(x, y)` would reveal, without exposing
+    /// anything: 1 for a gopher or a number, the whole cascade for an opening,
+    /// and 0 for exposed, flagged or protected cells or before the layout exists.
+    pub fn reveal_size(&self, x: usize, y: usize) -> usize {
+        let cell = &self.field[y][x];
+
+        if !self.generated || cell.is_exposed || cell.is_flagged || cell.is_protected { return 0 }
+        if cell.has_gopher() { return 1 }
+
+        let mut seen = HashSet::from([(x, y)]);
+        let mut queue = VecDeque::from([(x, y)]);
+
+        while let Some((x, y)) = queue.pop_front() {
+            for neighbor in self.cascade_targets(x, y) {
+                if !self.field[neighbor.1][neighbor.0].is_protected && seen.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
         }
-        
-        if x + 1 < w && y > 0 {
-            result.push((x + 1, y - 1));
+
+        seen.len()
+    }
+
+    pub fn try_expose_cell(&mut self, x: usize, y: usize) -> ExposeResult {
+        if let Some(result) = self.check_exposable(x, y) {
+            if result == ExposeResult::WasAlreadyExposed && self.config.double_click_chords() {
+                return self.chord(x, y).unwrap_or(result);
+            }
+
+            return result;
         }
-        
-        if x + 1 < w && y + 1 < h {
-            result.push((x + 1, y + 1));
+
+        self.flood(VecDeque::from([(x, y)]), usize::MAX);
+
+        self.expose_outcome()
+    }
+
+    /// Like `try_expose_cell`, but reveals at most `max_cells` cells and returns the
+    /// cells still waiting to be revealed, which can be passed to `resume_step`.
+    pub fn expose_step(&mut self, x: usize, y: usize, max_cells: usize) -> (ExposeResult, Vec<(usize, usize)>) {
+        if let Some(result) = self.check_exposable(x, y) { return (result, Vec::new()) }
+
+        let flood = self.flood(VecDeque::from([(x, y)]), max_cells);
+
+        (self.expose_outcome(), flood.pending)
+    }
+
+    /// Continues a flood from `pending`, normally the cells `expose_step` returned.
+    /// Cells off the board are ignored, and an unflagged gopher among them loses
+    /// the game just as exposing it would.
+    pub fn resume_step(&mut self, pending: Vec<(usize, usize)>, max_cells: usize) -> (ExposeResult, Vec<(usize, usize)>) {
+        self.clear_transients();
+
+        if self.state() != GameState::Playing { return (ExposeResult::GameOver, Vec::new()) }
+
+        let pending: VecDeque<(usize, usize)> = pending.into_iter().filter(|&(x, y)| self.in_bounds(x, y)).collect();
+
+        if pending.iter().any(|&(x, y)| {
+            let cell = &self.field[y][x];
+            cell.has_gopher() && !cell.is_exposed && !cell.is_flagged && !cell.is_protected
+        }) {
+            self.lose(LossReason::HitGopher);
+            return (ExposeResult::HasGopher, Vec::new());
         }
 
-        result
+        let flood = self.flood(pending, max_cells);
+
+        (self.expose_outcome(), flood.pending)
     }
-}
 
-impl<'a> IntoIterator for &'a GopherSweeper {
-    type Item = &'a Vec<Cell>;
-    type IntoIter = std::slice::Iter<'a, Vec<Cell>>;
+    /// Like `try_expose_cell`, also returning the revealed cells in flood-fill
+    /// (breadth-first) order so frontends can stagger a reveal animation.
+    pub fn expose_animated(&mut self, x: usize, y: usize) -> (ExposeResult, Vec<(usize, usize)>) {
+        if let Some(result) = self.check_exposable(x, y) { return (result, Vec::new()) }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.field.iter()
+        let flood = self.flood(VecDeque::from([(x, y)]), usize::MAX);
+
+        (self.expose_outcome(), flood.revealed)
     }
-}
 
-#[derive(Default)]
-pub struct Cell {
-    pub is_exposed: bool,
-    pub is_flagged: bool,
-    pub has_gopher: bool,
-    pub surrounding_gophers: u8,
-}
+    pub fn expose_with_changes(&mut self, x: usize, y: usize) -> ExposeReport {
+        if let Some(result) = self.check_exposable(x, y) {
+            return ExposeReport { result, revealed: Vec::new(), opened_region: false };
+        }
 
-pub enum ExposeResult {
-    Exposed,
-    WasAlreadyExposed,
-    IsFlagged,
-    HasGopher,
-    Win,
-}
+        let flood = self.flood(VecDeque::from([(x, y)]), usize::MAX);
 
-#[derive(Default)]
-pub enum FieldSize {
-    #[default]
-    Small,
-    Medium,
-    Big,
-    Custom {
-        width: usize,
-        height: usize,
-    },
-}
+        ExposeReport {
+            result: self.expose_outcome(),
+            revealed: flood.revealed,
+            opened_region: self.field[y][x].surrounding_gophers == 0,
+        }
+    }
 
-#[derive(Default)]
-pub enum Difficulty {
-    #[default]
-    Easy,
-    Normal,
-    Hard,
-    Custom {
-        gophers_percentage: f32
-    },
-}
+    /// Exposes the opening with the most cells (the earliest one on a tie) among
+    /// those with a cell left to expose, i.e. one that isn't exposed, flagged or
+    /// protected, generating a deferred layout first without a safe zone. Returns
+    /// `WasAlreadyExposed` when there is no such opening.
+    pub fn expose_largest_opening(&mut self) -> ExposeResult {
+        if !self.generated {
+            self.generate(None);
+        }
 
-#[derive(Default)]
-pub struct GameConfig {
-    field_size: FieldSize,
-    difficulty: Difficulty,
-}
+        let largest = self.openings()
+            .into_iter()
+            .filter_map(|opening| {
+                let start = opening.iter().copied().find(|&(x, y)| {
+                    let cell = &self.field[y][x];
+                    !cell.is_exposed && !cell.is_flagged && !cell.is_protected
+                })?;
 
-impl GameConfig {
-    pub fn new(field_size: FieldSize, difficulty: Difficulty) -> Self {
-        GameConfig {
-            field_size,
-            difficulty,
+                Some((opening.len(), start))
+            })
+            .rev()
+            .max_by_key(|&(len, _)| len);
+
+        match largest {
+            Some((_, (x, y))) => self.try_expose_cell(x, y),
+            None => ExposeResult::WasAlreadyExposed,
         }
     }
 
-    pub fn size(&self) -> (usize, usize) {
-        match self.field_size {
-            FieldSize::Small => SMALL,
-            FieldSize::Medium => MEDIUM,
-            FieldSize::Big => BIG,
-            FieldSize::Custom { width, height } => (width, height),
+    /// Exposes every unexposed, unflagged, unprotected cell in row `y`. Any such
+    /// gopher loses the game before anything is exposed, unless `skip_gophers`
+    /// is set, in which case gophers are just left alone. Returns
+    /// `WasAlreadyExposed` when nothing is left to expose.
+    pub fn expose_row(&mut self, y: usize, skip_gophers: bool) -> ExposeResult {
+        let (width, _) = self.config.size();
+        self.expose_all((0..width).map(|x| (x, y)).collect(), skip_gophers)
+    }
+
+    /// Like `expose_row`, for column `x`.
+    pub fn expose_column(&mut self, x: usize, skip_gophers: bool) -> ExposeResult {
+        let (_, height) = self.config.size();
+        self.expose_all((0..height).map(|y| (x, y)).collect(), skip_gophers)
+    }
+
+    fn expose_all(&mut self, cells: Vec<(usize, usize)>, skip_gophers: bool) -> ExposeResult {
+        self.clear_transients();
+
+        if self.state() != GameState::Playing { return ExposeResult::GameOver }
+
+        if !self.generated {
+            self.generate(None);
         }
+
+        let targets: Vec<(usize, usize)> = cells.into_iter()
+            .filter(|&(x, y)| {
+                let cell = &self.field[y][x];
+                !cell.is_exposed && !cell.is_flagged && !cell.is_protected
+            })
+            .collect();
+
+        if targets.is_empty() { return ExposeResult::WasAlreadyExposed }
+
+        if !skip_gophers && targets.iter().any(|&(x, y)| self.field[y][x].has_gopher()) {
+            self.lose(LossReason::HitGopher);
+            return ExposeResult::HasGopher;
+        }
+
+        let safe: VecDeque<(usize, usize)> = targets.into_iter()
+            .filter(|&(x, y)| !self.field[y][x].has_gopher())
+            .collect();
+
+        if safe.is_empty() { return ExposeResult::WasAlreadyExposed }
+
+        self.flood(safe, usize::MAX);
+
+        self.expose_outcome()
+    }
+
+    /// Tries to expose every cell on the Bresenham line from `from` to `to`, both
+    /// included, as a drag gesture would. Returns one result per cell tried,
+    /// stopping after the first gopher or once the game is over.
+    pub fn expose_line(&mut self, from: (usize, usize), to: (usize, usize)) -> Vec<ExposeResult> {
+        let mut results = Vec::new();
+
+        for (x, y) in line_cells(from, to) {
+            let result = self.try_expose_cell(x, y);
+            results.push(result);
+
+            if matches!(result, ExposeResult::HasGopher | ExposeResult::GameOver) { break }
+        }
+
+        results
+    }
+
+    /// Exposes every unexposed, unflagged neighbor of the exposed number at `(x, y)`
+    /// once as many of its neighbors are flagged as its number says. In flagless
+    /// mode, neighbors that are certainly gophers count as flagged. Returns `None`
+    /// when the number isn't satisfied or there is nothing left to expose.
+    pub fn chord(&mut self, x: usize, y: usize) -> Option<ExposeResult> {
+        let targets = self.chord_targets(x, y)?;
+
+        self.clear_transients();
+
+        if self.state() != GameState::Playing { return Some(ExposeResult::GameOver) }
+
+        if targets.iter().any(|&(x, y)| self.field[y][x].has_gopher()) {
+            self.lose(LossReason::HitGopher);
+            return Some(ExposeResult::HasGopher);
+        }
+
+        self.flood(targets.into(), usize::MAX);
+
+        Some(self.expose_outcome())
+    }
+
+    /// Whether chording `(x, y)` would hit a gopher because a flag around it is
+    /// misplaced. This reveals layout information, so it is meant for assist modes.
+    pub fn chord_would_lose(&self, x: usize, y: usize) -> bool {
+        self.chord_targets(x, y)
+            .is_some_and(|targets| targets.iter().any(|&(x, y)| self.field[y][x].has_gopher()))
+    }
+
+    fn chord_targets(&self, x: usize, y: usize) -> Option<Vec<(usize, usize)>> {
+        let cell = &self.field[y][x];
+
+        if !cell.is_exposed || cell.has_gopher() { return None }
+
+        let certain_gophers: HashSet<(usize, usize)> = if self.config.flagless() {
+            self.certain_moves().gophers.into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+
+        let is_marked = |&(x, y): &(usize, usize)| self.field[y][x].is_flagged || certain_gophers.contains(&(x, y));
+        let hidden: Vec<(usize, usize)> = self.surrounding_cells_coords(x, y)
+            .into_iter()
+            .filter(|&(x, y)| !self.field[y][x].is_exposed)
+            .collect();
+
+        let marked = hidden.iter().filter(|cell| is_marked(cell)).count();
+        let targets: Vec<(usize, usize)> = hidden.into_iter()
+            .filter(|cell| !is_marked(cell) && !self.field[cell.1][cell.0].is_protected)
+            .collect();
+
+        if marked != cell.surrounding_gophers as usize || targets.is_empty() {
+            return None;
+        }
+
+        Some(targets)
+    }
+
+    /// Resets every cell's `just_revealed` marker. This happens automatically at
+    /// the start of each expose or flag action.
+    pub fn clear_transients(&mut self) {
+        for (x, y) in self.transients.drain(..) {
+            self.field[y][x].just_revealed = false;
+        }
+    }
+
+    fn check_exposable(&mut self, x: usize, y: usize) -> Option<ExposeResult> {
+        self.clear_transients();
+
+        if self.state() != GameState::Playing { return Some(ExposeResult::GameOver) }
+
+        if !self.generated && !self.field[y][x].is_flagged && !self.field[y][x].is_protected {
+            self.generate(Some((x, y)));
+        }
+
+        let cell = &self.field[y][x];
+
+        if cell.is_protected { return Some(ExposeResult::Protected) }
+        if cell.is_exposed { return Some(ExposeResult::WasAlreadyExposed) }
+        if cell.is_flagged { return Some(ExposeResult::IsFlagged) }
+        if cell.has_gopher() {
+            self.lose(LossReason::HitGopher);
+            return Some(ExposeResult::HasGopher);
+        }
+
+        None
+    }
+
+    fn expose_outcome(&mut self) -> ExposeResult {
+        if self.state() != GameState::Playing { return ExposeResult::GameOver }
+        if self.remaining_cells == 0 && self.config.win_condition() == WinCondition::ClearAllSafe {
+            self.won = true;
+            self.apply_reveal_policy(self.config.reveal_on_win());
+            return ExposeResult::Win;
+        }
+
+        ExposeResult::Exposed
+    }
+
+    fn lose(&mut self, reason: LossReason) {
+        self.loss.get_or_insert(reason);
+        self.apply_reveal_policy(self.config.reveal_on_loss());
+    }
+
+    /// A loss takes precedence over a win, e.g. when the time runs out after the
+    /// board was cleared but before it was noticed.
+    pub fn state(&self) -> GameState {
+        match self.loss {
+            Some(reason) => GameState::Lost(reason),
+            None if self.won => GameState::Won,
+            None => GameState::Playing,
+        }
+    }
+
+    /// False once the game is lost. Any running game can still be won, even under
+    /// `WinCondition::FlagAllGophers`, since exposing skips flagged cells and ends
+    /// the game on a gopher.
+    pub fn is_winnable(&self) -> bool {
+        !matches!(self.state(), GameState::Lost(_))
+    }
+
+    /// Advances the time-attack clock to `now`. The library keeps no clock of its
+    /// own: the limit is measured from the first tick after the first expose, so
+    /// callers should tick regularly while the game is being played. Once the limit
+    /// passes the game is lost with `LossReason::TimeExpired`.
+    pub fn tick(&mut self, now: Instant) -> GameState {
+        if let (Some(limit), GameState::Playing, true) = (self.config.time_limit(), self.state(), self.started) {
+            let started_at = *self.started_at.get_or_insert(now);
+
+            if now.saturating_duration_since(started_at) >= limit {
+                self.lose(LossReason::TimeExpired);
+            }
+        }
+
+        self.state()
+    }
+
+    fn apply_reveal_policy(&mut self, policy: RevealPolicy) {
+        for (x, y) in self.cells_where(Cell::has_gopher) {
+            let cell = &mut self.field[y][x];
+
+            match policy {
+                RevealPolicy::Leave => {}
+                RevealPolicy::Flag if !cell.is_flagged => {
+                    cell.is_flagged = true;
+                    self.notify(x, y, CellEventKind::Flagged);
+                }
+                RevealPolicy::Reveal => {
+                    self.mark_exposed(x, y);
+                }
+                RevealPolicy::Flag => {}
+            }
+        }
+    }
+
+    /// The only place a cell goes from unexposed to exposed. Exposing an exposed
+    /// cell again does nothing, so overlapping exposes can't throw the remaining
+    /// cell count off.
+    fn mark_exposed(&mut self, x: usize, y: usize) {
+        let cell = &mut self.field[y][x];
+
+        if cell.is_exposed { return }
+
+        cell.is_exposed = true;
+        cell.just_revealed = true;
+
+        if !cell.has_gopher() {
+            self.remaining_cells -= 1;
+        }
+
+        self.exposed_cells += 1;
+
+        self.transients.push((x, y));
+        self.notify(x, y, CellEventKind::Exposed);
+    }
+
+    /// Registers a callback invoked whenever a cell is exposed, flagged or
+    /// unflagged, including cells covered again by `restore` or
+    /// `retry_from_here`. Clones of the board start without an observer.
+    pub fn set_observer(&mut self, observer: Box<dyn FnMut(CellEvent) + Send>) {
+        self.observer = Observer(Some(observer));
+    }
+
+    fn notify(&mut self, x: usize, y: usize, kind: CellEventKind) {
+        if let Some(observer) = &mut self.observer.0 {
+            observer(CellEvent { x, y, kind });
+        }
+    }
+
+    pub fn visible_cell(&self, x: usize, y: usize) -> VisibleCell {
+        let cell = &self.field[y][x];
+
+        if cell.is_exposed && cell.has_gopher() {
+            VisibleCell::Gopher
+        } else if cell.is_exposed {
+            VisibleCell::Exposed(cell.surrounding_gophers)
+        } else if cell.is_flagged {
+            VisibleCell::Flagged
+        } else if self.is_fogged(x, y) {
+            VisibleCell::Obscured
+        } else {
+            VisibleCell::Hidden
+        }
+    }
+
+    fn is_fogged(&self, x: usize, y: usize) -> bool {
+        let Some(radius) = self.config.fog_radius() else { return false };
+        let (width, height) = self.config.size();
+
+        let min_x = x.saturating_sub(radius);
+        let min_y = y.saturating_sub(radius);
+        let max_x = (x + radius).min(width - 1);
+        let max_y = (y + radius).min(height - 1);
+
+        !(min_y..=max_y).any(|y| (min_x..=max_x).any(|x| self.field[y][x].is_exposed))
+    }
+
+    pub fn snapshot(&self) -> BoardSnapshot {
+        let (width, height) = self.config.size();
+
+        BoardSnapshot {
+            cells: (0..height)
+                .map(|y| (0..width).map(|x| self.visible_cell(x, y)).collect())
+                .collect(),
+            remaining_cells: self.remaining_cells,
+        }
+    }
+
+    /// The board as the player sees it, indexed `[y][x]`: `#` hidden, `~` fogged,
+    /// `F` flagged, `*` an exposed gopher, `.` an empty cell and otherwise the
+    /// number plus `GameConfig::number_offset` as a base-36 digit (`?` when the
+    /// result falls outside `0..36`). `Display` prints one line per row.
+    pub fn render_chars(&self) -> Vec<Vec<char>> {
+        let (width, height) = self.config.size();
+        let offset = self.config.number_offset() as i16;
+
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| match self.visible_cell(x, y) {
+                        VisibleCell::Hidden => '#',
+                        VisibleCell::Obscured => '~',
+                        VisibleCell::Flagged => 'F',
+                        VisibleCell::Gopher => '*',
+                        VisibleCell::Exposed(0) => '.',
+                        VisibleCell::Exposed(number) => u32::try_from(number as i16 + offset)
+                            .ok()
+                            .and_then(|shown| char::from_digit(shown, 36))
+                            .unwrap_or('?'),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Cells whose `visible_cell` differs from `snapshot`, row by row. Cells the
+    /// snapshot doesn't cover count as changed.
+    pub fn changed_since(&self, snapshot: &BoardSnapshot) -> Vec<(usize, usize)> {
+        let (width, height) = self.config.size();
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| snapshot.cells.get(y).and_then(|row| row.get(x)) != Some(&self.visible_cell(x, y)))
+            .collect()
+    }
+
+    pub fn cells_where<F: Fn(&Cell) -> bool>(&self, pred: F) -> Vec<(usize, usize)> {
+        let (width, height) = self.config.size();
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| pred(&self.field[y][x]))
+            .collect()
+    }
+
+    /// A 64-bit FNV-1a hash of the width and height (as little-endian `u64`s)
+    /// followed by every cell's gopher count in row-major order. Identifies the
+    /// exact board, where `config_key` only identifies its settings.
+    pub fn layout_hash(&self) -> u64 {
+        let (width, height) = self.config.size();
+
+        let mut bytes = Vec::with_capacity(16 + width * height);
+        bytes.extend_from_slice(&(width as u64).to_le_bytes());
+        bytes.extend_from_slice(&(height as u64).to_le_bytes());
+        bytes.extend(self.field.iter().flatten().map(|cell| cell.gopher_count));
+
+        fnv1a(&bytes)
+    }
+
+    /// Whether both boards are the same puzzle: equal dimensions and gopher counts
+    /// in every cell, whatever the exposed and flagged state.
+    pub fn same_layout(&self, other: &GopherSweeper) -> bool {
+        self.config.size() == other.config.size()
+            && self.field.iter()
+                .flatten()
+                .zip(other.field.iter().flatten())
+                .all(|(a, b)| a.gopher_count == b.gopher_count)
+    }
+
+    pub fn flags_placed(&self) -> usize {
+        self.field.iter().flatten().filter(|cell| cell.is_flagged).count()
+    }
+
+    /// Whether the flagged cells are exactly the gopher cells. This reveals layout
+    /// information, so it is meant for end-of-game checks.
+    pub fn flags_match_gophers(&self) -> bool {
+        self.generated && self.field.iter().flatten().all(|cell| cell.is_flagged == cell.has_gopher())
+    }
+
+    /// Counts flags placed on actual gophers. This reveals layout information, so
+    /// it is meant for assist modes and end-of-game summaries.
+    pub fn correct_flags(&self) -> usize {
+        self.field.iter()
+            .flatten()
+            .filter(|cell| cell.is_flagged && cell.has_gopher())
+            .count()
+    }
+
+    /// The unexposed cells along the edge of the opened areas, where an opened
+    /// area is an exposed empty region together with the exposed numbers around
+    /// it. Unlike the frontier, numbers exposed on their own don't count. Row-major.
+    pub fn region_borders(&self) -> Vec<(usize, usize)> {
+        let is_exposed_empty = |x: usize, y: usize| {
+            let cell = &self.field[y][x];
+            cell.is_exposed && !cell.has_gopher() && cell.surrounding_gophers == 0
+        };
+        let is_opened = |x: usize, y: usize| {
+            self.field[y][x].is_exposed
+                && (is_exposed_empty(x, y) || self.surrounding_cells_coords(x, y).into_iter().any(|(x, y)| is_exposed_empty(x, y)))
+        };
+
+        self.cells_where(|cell| !cell.is_exposed)
+            .into_iter()
+            .filter(|&(x, y)| self.surrounding_cells_coords(x, y).into_iter().any(|(x, y)| is_opened(x, y)))
+            .collect()
+    }
+
+    pub fn redundant_flags(&self) -> Vec<(usize, usize)> {
+        let (width, height) = self.config.size();
+        let mut result = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = &self.field[y][x];
+
+                if !cell.is_flagged || cell.is_exposed {
+                    continue;
+                }
+
+                let is_required = self.surrounding_cells_coords(x, y)
+                    .into_iter()
+                    .any(|(nx, ny)| {
+                        let neighbor = &self.field[ny][nx];
+
+                        neighbor.is_exposed
+                            && neighbor.surrounding_gophers > 0
+                            && self.flagged_neighbors(nx, ny) == neighbor.surrounding_gophers as usize
+                    });
+
+                if !is_required {
+                    result.push((x, y));
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn exposed_neighbor_number_sum(&self, x: usize, y: usize) -> u32 {
+        self.surrounding_cells_coords(x, y)
+            .into_iter()
+            .map(|(x, y)| &self.field[y][x])
+            .filter(|cell| cell.is_exposed && !cell.has_gopher())
+            .map(|cell| cell.surrounding_gophers as u32)
+            .sum()
+    }
+
+    /// Every cell's number indexed `[y][x]`, whether exposed or not, with `None`
+    /// for gopher cells. Meant for level editors where the whole board is visible.
+    pub fn editor_overlay(&self) -> Vec<Vec<Option<u8>>> {
+        self.field.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| if cell.has_gopher() { None } else { Some(cell.surrounding_gophers) })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A rough danger overlay indexed `[y][x]`: each unexposed cell's
+    /// `exposed_neighbor_number_sum`, scaled so the highest is 1.0. Exposed cells
+    /// are 0.0. A presentation aid, not a gopher probability.
+    pub fn danger_heatmap(&self) -> Vec<Vec<f32>> {
+        let (width, height) = self.config.size();
+
+        let sums: Vec<Vec<u32>> = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| if self.field[y][x].is_exposed { 0 } else { self.exposed_neighbor_number_sum(x, y) })
+                    .collect()
+            })
+            .collect();
+        let highest = sums.iter().flatten().copied().max().unwrap_or(0).max(1) as f32;
+
+        sums.into_iter()
+            .map(|row| row.into_iter().map(|sum| sum as f32 / highest).collect())
+            .collect()
+    }
+
+    fn flagged_neighbors(&self, x: usize, y: usize) -> usize {
+        self.surrounding_cells_coords(x, y)
+            .into_iter()
+            .filter(|&(x, y)| self.field[y][x].is_flagged)
+            .count()
+    }
+
+    fn flood(
+        &mut self,
+        mut queue: VecDeque<(usize, usize)>,
+        max_cells: usize,
+    ) -> Flood {
+        let mut revealed = Vec::new();
+
+        while let Some((x, y)) = queue.pop_front() {
+            if self.field[y][x].is_exposed || self.field[y][x].is_flagged || self.field[y][x].is_protected { continue }
+
+            if revealed.len() == max_cells {
+                queue.push_front((x, y));
+                break;
+            }
+
+            self.mark_exposed(x, y);
+            self.started = true;
+            revealed.push((x, y));
+
+            queue.extend(self.cascade_targets(x, y));
+        }
+
+        let mut seen = HashSet::new();
+        let pending = queue.into_iter()
+            .filter(|&(x, y)| !self.field[y][x].is_exposed && seen.insert((x, y)))
+            .collect();
+
+        Flood { revealed, pending }
+    }
+
+    /// The unexposed neighbors a flood continues into after revealing `(x, y)`.
+    /// Flagged cells are never cascaded into, so a flag on an empty cell also
+    /// stops the cascade from spreading past it. Neither are gophers, which
+    /// anti-gophers can leave next to a 0.
+    fn cascade_targets(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        if self.field[y][x].surrounding_gophers > 0 { return Vec::new() }
+
+        self.surrounding_cells_coords(x, y)
+            .into_iter()
+            .filter(|&(x, y)| {
+                let neighbor = &self.field[y][x];
+                !neighbor.is_exposed
+                    && !neighbor.is_flagged
+                    && !neighbor.has_gopher()
+                    && (neighbor.surrounding_gophers == 0 || self.config.cascade_reveals_borders())
+            })
+            .collect()
+    }
+
+    /// The `(dx, dy)` offsets of a cell's neighbors under `adjacency`, in the
+    /// order the crate visits them.
+    pub fn neighbor_offsets(adjacency: Adjacency) -> &'static [(i8, i8)] {
+        match adjacency {
+            Adjacency::Standard => &NEIGHBOR_OFFSETS,
+            Adjacency::Diagonal => &NEIGHBOR_OFFSETS[4..],
+        }
+    }
+
+    /// The cells at exactly Chebyshev distance `d` from `(cx, cy)` that lie on the
+    /// board, row by row. `d = 0` gives the center alone.
+    pub fn ring(&self, cx: usize, cy: usize, d: usize) -> Vec<(usize, usize)> {
+        let (width, height) = self.config.size();
+        let xs = cx.saturating_sub(d)..=cx.saturating_add(d).min(width.saturating_sub(1));
+        let ys = cy.saturating_sub(d)..=cy.saturating_add(d).min(height.saturating_sub(1));
+
+        ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| x.abs_diff(cx).max(y.abs_diff(cy)) == d)
+            .collect()
+    }
+
+    fn surrounding_cells_coords(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let (w, h) = self.config.size();
+
+        GopherSweeper::neighbor_offsets(self.config.adjacency())
+            .iter()
+            .filter_map(|&(dx, dy)| Some((x.checked_add_signed(dx as isize)?, y.checked_add_signed(dy as isize)?)))
+            .filter(|&(x, y)| x < w && y < h)
+            .collect()
+    }
+}
+
+struct Observer(Option<Box<dyn FnMut(CellEvent) + Send>>);
+
+impl Clone for Observer {
+    fn clone(&self) -> Self {
+        Observer(None)
+    }
+}
+
+/// The board's cells in a single row-major buffer, indexed `[y][x]` like nested rows.
+#[derive(Clone)]
+struct Field {
+    cells: Vec<Cell>,
+    width: usize,
+}
+
+impl Field {
+    fn new(width: usize, height: usize) -> Self {
+        Field { cells: vec![Cell::default(); width * height], width }
+    }
+
+    fn iter(&self) -> std::slice::Chunks<'_, Cell> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    fn iter_mut(&mut self) -> std::slice::ChunksMut<'_, Cell> {
+        self.cells.chunks_mut(self.width.max(1))
+    }
+}
+
+impl std::ops::Index<usize> for Field {
+    type Output = [Cell];
+
+    fn index(&self, y: usize) -> &[Cell] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+
+impl std::ops::IndexMut<usize> for Field {
+    fn index_mut(&mut self, y: usize) -> &mut [Cell] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+
+struct Flood {
+    revealed: Vec<(usize, usize)>,
+    pending: Vec<(usize, usize)>,
+}
+
+fn line_cells((x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> Vec<(usize, usize)> {
+    let (x0, y0, x1, y1) = (x0 as isize, y0 as isize, x1 as isize, y1 as isize);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    let mut result = vec![(x as usize, y as usize)];
+
+    while (x, y) != (x1, y1) {
+        let doubled = 2 * error;
+
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+
+        result.push((x as usize, y as usize));
+    }
+
+    result
+}
+
+/// Maps one `u64` draw onto `0..n` by widening multiplication. Seeded boards
+/// depend on this together with `ChaCha8Rng`, so neither may change without
+/// changing every seeded layout.
+fn random_below<R: Rng>(rng: &mut R, n: usize) -> usize {
+    ((rng.next_u64() as u128 * n as u128) >> 64) as usize
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn zone_area(x: usize, y: usize, radius: usize, margin: usize, width: usize, height: usize) -> usize {
+    let span = |center: usize, length: usize| {
+        (center + radius + 1).min(length.saturating_sub(margin)).saturating_sub(center.saturating_sub(radius).max(margin))
+    };
+
+    span(x, width) * span(y, height)
+}
+
+impl<'a> IntoIterator for &'a GopherSweeper {
+    type Item = &'a [Cell];
+    type IntoIter = std::slice::Chunks<'a, Cell>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.field.iter()
+    }
+}
+
+impl fmt::Display for GopherSweeper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.render_chars().into_iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", row.into_iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub is_exposed: bool,
+    pub is_flagged: bool,
+    pub gopher_count: u8,
+    pub surrounding_gophers: u8,
+    /// Set on cells revealed by the most recent expose and cleared when the next
+    /// action starts (or by `clear_transients`); not part of the persistent state.
+    pub just_revealed: bool,
+    pub kind: CellKind,
+    /// The player's note, if any; see `GopherSweeper::set_note`. Boxed to keep
+    /// cells small, since most never get one.
+    pub note: Option<Box<CellNote>>,
+    /// A cosmetic tag on gopher cells, drawn from
+    /// `GameConfig::gopher_variant_weights`; always 0 on safe cells.
+    pub variant: u8,
+    /// See `GopherSweeper::set_protected`.
+    pub is_protected: bool,
+}
+
+impl Cell {
+    pub fn has_gopher(&self) -> bool {
+        self.gopher_count > 0
+    }
+}
+
+/// A note a player keeps on a cell, such as the numbers it might hold. Stored
+/// for frontends and ignored by the game logic.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CellNote {
+    pub text: String,
+    pub candidates: BTreeSet<u8>,
+}
+
+static EMPTY_NOTE: CellNote = CellNote { text: String::new(), candidates: BTreeSet::new() };
+
+/// `AntiGopher` cells are safe, but take one off the number of every neighbor,
+/// down to zero. The solver doesn't know about them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    #[default]
+    Normal,
+    AntiGopher,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibleCell {
+    Hidden,
+    Obscured,
+    Flagged,
+    Exposed(u8),
+    Gopher,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardSnapshot {
+    pub cells: Vec<Vec<VisibleCell>>,
+    pub remaining_cells: usize,
+}
+
+impl BoardSnapshot {
+    pub fn cell(&self, x: usize, y: usize) -> VisibleCell {
+        self.cells[y][x]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellEvent {
+    pub x: usize,
+    pub y: usize,
+    pub kind: CellEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellEventKind {
+    Exposed,
+    Unexposed,
+    Flagged,
+    Unflagged,
+}
+
+/// The outcome of an expose along with the cells it revealed. `opened_region`
+/// tells an opening (a cascade from an empty cell) apart from a single number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExposeReport {
+    pub result: ExposeResult,
+    pub revealed: Vec<(usize, usize)>,
+    pub opened_region: bool,
+}
+
+impl ExposeReport {
+    /// `revealed` in row-major order rather than flood order, for comparisons
+    /// that shouldn't depend on how the flood walks the board.
+    pub fn revealed_sorted(&self) -> Vec<(usize, usize)> {
+        let mut cells = self.revealed.clone();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        cells
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleFlagResult {
+    Flagged,
+    Unflagged,
+    CellWasExposed,
+    GameNotStarted,
+    /// The flag completed a `WinCondition::FlagAllGophers` win, or a chord that
+    /// `GameConfig::with_assist_on_correct_flag` made for it cleared the board.
+    Win,
+    /// Flagging a satisfied number chorded it instead; see
+    /// `GameConfig::with_exposed_flag_chords`.
+    Chorded(ExposeResult),
+    /// The game was already won or lost, so nothing changed.
+    GameOver,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost(LossReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LossReason {
+    HitGopher,
+    TimeExpired,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExposeResult {
+    Exposed,
+    WasAlreadyExposed,
+    IsFlagged,
+    HasGopher,
+    Win,
+    Protected,
+    /// The game was already won or lost, so nothing changed.
+    GameOver,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FieldSize {
+    #[default]
+    Small,
+    Medium,
+    Big,
+    Custom {
+        width: usize,
+        height: usize,
+    },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Normal,
+    Hard,
+    Custom {
+        gophers_percentage: f32
+    },
+    Exact {
+        gophers: usize,
+    },
+}
+
+/// What happens to the gophers when a game is won or lost: left as they are,
+/// flagged, or exposed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum RevealPolicy {
+    #[default]
+    Leave,
+    Flag,
+    Reveal,
+}
+
+/// How a game is won: by exposing every safe cell, or by flagging exactly the
+/// gopher cells, in which case exposing the last safe cell doesn't win by itself.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WinCondition {
+    #[default]
+    ClearAllSafe,
+    FlagAllGophers,
+}
+
+/// Which cells count as neighbors for numbers and cascades: all eight
+/// surrounding cells, or only the four diagonal ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Adjacency {
+    #[default]
+    Standard,
+    Diagonal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GameConfig {
+    field_size: FieldSize,
+    difficulty: Difficulty,
+    safe_radius: Option<usize>,
+    max_gophers_per_cell: u8,
+    fog_radius: Option<usize>,
+    reveal_on_win: RevealPolicy,
+    reveal_on_loss: RevealPolicy,
+    flag_after_first_expose: bool,
+    adjacency: Adjacency,
+    cascade_reveals_borders: bool,
+    gopher_margin: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    max_cells: usize,
+    flagless: bool,
+    anti_gophers: usize,
+    exposed_flag_chords: bool,
+    time_limit: Option<Duration>,
+    win_condition: WinCondition,
+    assist_on_correct_flag: bool,
+    max_cell_number: Option<u8>,
+    double_click_chords: bool,
+    gopher_variant_weights: Vec<u32>,
+    number_offset: i8,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig::new(FieldSize::default(), Difficulty::default())
+    }
+}
+
+impl GameConfig {
+    pub fn new(field_size: FieldSize, difficulty: Difficulty) -> Self {
+        GameConfig {
+            field_size,
+            difficulty,
+            safe_radius: None,
+            max_gophers_per_cell: 1,
+            fog_radius: None,
+            reveal_on_win: RevealPolicy::Leave,
+            reveal_on_loss: RevealPolicy::Leave,
+            flag_after_first_expose: false,
+            adjacency: Adjacency::Standard,
+            cascade_reveals_borders: true,
+            gopher_margin: 0,
+            max_cells: DEFAULT_MAX_CELLS,
+            flagless: false,
+            anti_gophers: 0,
+            exposed_flag_chords: false,
+            time_limit: None,
+            win_condition: WinCondition::ClearAllSafe,
+            assist_on_correct_flag: false,
+            max_cell_number: None,
+            double_click_chords: false,
+            gopher_variant_weights: Vec::new(),
+            number_offset: 0,
+        }
+    }
+
+    /// Defers planting gophers until the first expose, keeping every cell within
+    /// `radius` (Chebyshev distance) of it gopher-free when the gopher count allows.
+    pub fn with_safe_radius(mut self, radius: usize) -> Self {
+        self.safe_radius = Some(radius);
+        self
+    }
+
+    pub fn safe_radius(&self) -> Option<usize> {
+        self.safe_radius
+    }
+
+    /// Allows "heavy" cells holding up to `max` gophers; numbers then sum the
+    /// gophers around a cell rather than counting gopher cells.
+    pub fn with_max_gophers_per_cell(mut self, max: u8) -> Self {
+        self.max_gophers_per_cell = max.max(1);
+        self
+    }
+
+    pub fn max_gophers_per_cell(&self) -> u8 {
+        self.max_gophers_per_cell
+    }
+
+    /// Makes views report unexposed cells farther than `radius` from every
+    /// exposed cell as `VisibleCell::Obscured`; flags stay visible.
+    pub fn with_fog_radius(mut self, radius: usize) -> Self {
+        self.fog_radius = Some(radius);
+        self
+    }
+
+    pub fn fog_radius(&self) -> Option<usize> {
+        self.fog_radius
+    }
+
+    pub fn with_reveal_on_win(mut self, policy: RevealPolicy) -> Self {
+        self.reveal_on_win = policy;
+        self
+    }
+
+    pub fn reveal_on_win(&self) -> RevealPolicy {
+        self.reveal_on_win
+    }
+
+    pub fn with_reveal_on_loss(mut self, policy: RevealPolicy) -> Self {
+        self.reveal_on_loss = policy;
+        self
+    }
+
+    pub fn reveal_on_loss(&self) -> RevealPolicy {
+        self.reveal_on_loss
+    }
+
+    /// Rejects flags with `ToggleFlagResult::GameNotStarted` until a cell has been
+    /// exposed, which is when a deferred layout actually exists.
+    pub fn with_flag_after_first_expose(mut self, enabled: bool) -> Self {
+        self.flag_after_first_expose = enabled;
+        self
+    }
+
+    pub fn flag_after_first_expose(&self) -> bool {
+        self.flag_after_first_expose
+    }
+
+    pub fn with_adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    pub fn adjacency(&self) -> Adjacency {
+        self.adjacency
+    }
+
+    /// Whether cascades from empty cells also reveal the numbers bordering the
+    /// empty region (the default) or stop at them, leaving them hidden.
+    pub fn with_cascade_reveals_borders(mut self, enabled: bool) -> Self {
+        self.cascade_reveals_borders = enabled;
+        self
+    }
+
+    pub fn cascade_reveals_borders(&self) -> bool {
+        self.cascade_reveals_borders
+    }
+
+    /// Keeps gophers at least `margin` cells away from every edge. `validate`
+    /// rejects configs whose interior can't hold every gopher; `GopherSweeper::new`
+    /// places as many as fit.
+    pub fn with_gopher_margin(mut self, margin: usize) -> Self {
+        self.gopher_margin = margin;
+        self
+    }
+
+    pub fn gopher_margin(&self) -> usize {
+        self.gopher_margin
+    }
+
+    /// The largest board `validate` accepts, 16M cells by default. It isn't
+    /// serialized, so configs read from untrusted input keep the default.
+    pub fn with_max_cells(mut self, max: usize) -> Self {
+        self.max_cells = max;
+        self
+    }
+
+    pub fn max_cells(&self) -> usize {
+        self.max_cells
+    }
+
+    /// Lets `chord` treat neighbors that are certainly gophers as flagged, for
+    /// players who never place flags.
+    pub fn with_flagless(mut self, enabled: bool) -> Self {
+        self.flagless = enabled;
+        self
+    }
+
+    pub fn flagless(&self) -> bool {
+        self.flagless
+    }
+
+    /// Makes `toggle_flag` on an exposed, satisfied number chord it, returning
+    /// `ToggleFlagResult::Chorded`, like right-clicking a number in most clients.
+    pub fn with_exposed_flag_chords(mut self, enabled: bool) -> Self {
+        self.exposed_flag_chords = enabled;
+        self
+    }
+
+    pub fn exposed_flag_chords(&self) -> bool {
+        self.exposed_flag_chords
+    }
+
+    /// Makes `try_expose_cell` on an exposed, satisfied number chord it instead of
+    /// returning `ExposeResult::WasAlreadyExposed`, like double-clicking a number.
+    pub fn with_double_click_chords(mut self, enabled: bool) -> Self {
+        self.double_click_chords = enabled;
+        self
+    }
+
+    pub fn double_click_chords(&self) -> bool {
+        self.double_click_chords
+    }
+
+    pub fn with_win_condition(mut self, condition: WinCondition) -> Self {
+        self.win_condition = condition;
+        self
+    }
+
+    pub fn win_condition(&self) -> WinCondition {
+        self.win_condition
+    }
+
+    /// A training mode: flagging an actual gopher chords every exposed number
+    /// around it that the flag satisfies, as long as doing so is safe. This looks
+    /// at the layout, so the flag's correctness leaks through the exposes.
+    pub fn with_assist_on_correct_flag(mut self, enabled: bool) -> Self {
+        self.assist_on_correct_flag = enabled;
+        self
+    }
+
+    pub fn assist_on_correct_flag(&self) -> bool {
+        self.assist_on_correct_flag
+    }
+
+    /// Regenerates layouts where any cell's number would exceed `max`, up to a
+    /// bounded number of attempts. `GopherSweeper::try_new` reports running out of
+    /// attempts as `ConfigError::GenerationFailed`; the other constructors keep
+    /// the last attempt.
+    pub fn with_max_cell_number(mut self, max: u8) -> Self {
+        self.max_cell_number = Some(max);
+        self
+    }
+
+    pub fn max_cell_number(&self) -> Option<u8> {
+        self.max_cell_number
+    }
+
+    /// Loses the game with `LossReason::TimeExpired` once `limit` has passed; see
+    /// `GopherSweeper::tick`.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    /// Places `count` `CellKind::AntiGopher` cells on gopher-free cells.
+    pub fn with_anti_gophers(mut self, count: usize) -> Self {
+        self.anti_gophers = count;
+        self
+    }
+
+    pub fn anti_gophers(&self) -> usize {
+        self.anti_gophers
+    }
+
+    /// Tags each generated gopher with variant `i` with probability proportional
+    /// to `weights[i]`. Variants only matter to frontends; without at least two
+    /// weights every gopher gets variant 0.
+    pub fn with_gopher_variant_weights(mut self, weights: Vec<u32>) -> Self {
+        self.gopher_variant_weights = weights;
+        self
+    }
+
+    pub fn gopher_variant_weights(&self) -> &[u32] {
+        &self.gopher_variant_weights
+    }
+
+    /// Shifts the numbers `render_chars` and `Display` show by `offset`; the game
+    /// itself keeps using the true counts.
+    pub fn with_number_offset(mut self, offset: i8) -> Self {
+        self.number_offset = offset;
+        self
+    }
+
+    pub fn number_offset(&self) -> i8 {
+        self.number_offset
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let (width, height) = self.size();
+
+        if width.checked_mul(height).is_none_or(|cells| cells > self.max_cells) {
+            return Err(ConfigError::TooManyCells { width, height, max_cells: self.max_cells });
+        }
+
+        let interior = width.saturating_sub(2 * self.gopher_margin) * height.saturating_sub(2 * self.gopher_margin);
+        let capacity = interior * self.max_gophers_per_cell as usize;
+
+        if self.gophers() > capacity {
+            return Err(ConfigError::TooManyGophers { gophers: self.gophers(), capacity });
+        }
+
+        Ok(())
+    }
+
+    /// `levels` configs based on `base` that get steadily harder: from the first
+    /// level to the last, the gopher density rises linearly from the base density
+    /// to 25% (or stays put if it is already higher) and both dimensions grow
+    /// linearly up to 1.5 times the base size. Every other setting is kept.
+    pub fn campaign(base: GameConfig, levels: usize) -> Vec<GameConfig> {
+        let (width, height) = base.size();
+        let base_density = base.gophers() as f32 / (width * height).max(1) as f32;
+        let final_density = base_density.max(CAMPAIGN_FINAL_DENSITY);
+
+        (0..levels)
+            .map(|level| {
+                let progress = if levels > 1 { level as f32 / (levels - 1) as f32 } else { 0.0 };
+                let scale = 1.0 + (CAMPAIGN_FINAL_SCALE - 1.0) * progress;
+
+                let mut config = base.clone();
+                config.field_size = FieldSize::Custom {
+                    width: (width as f32 * scale).round() as usize,
+                    height: (height as f32 * scale).round() as usize,
+                };
+                config.difficulty = Difficulty::Custom {
+                    gophers_percentage: base_density + (final_density - base_density) * progress,
+                };
+                config
+            })
+            .collect()
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        match self.field_size {
+            FieldSize::Small => SMALL,
+            FieldSize::Medium => MEDIUM,
+            FieldSize::Big => BIG,
+            FieldSize::Custom { width, height } => (width, height),
+        }
+    }
+
+    pub fn config_key(&self) -> ConfigKey {
+        let (width, height) = self.size();
+
+        ConfigKey {
+            width,
+            height,
+            gophers: self.gophers(),
+            max_gophers_per_cell: self.max_gophers_per_cell,
+            safe_radius: self.safe_radius,
+            adjacency: self.adjacency,
+            gopher_margin: self.gopher_margin,
+            anti_gophers: self.anti_gophers,
+            max_cell_number: self.max_cell_number,
+            win_condition: self.win_condition,
+            time_limit: self.time_limit,
+            cascade_reveals_borders: self.cascade_reveals_borders,
+            flagless: self.flagless,
+        }
+    }
+
+    /// The most gophers that still leave a playable board: at least one safe cell,
+    /// or a full safe zone around the first click when a safe radius is set, with
+    /// none of them inside the gopher margin. Only the part of the safe zone that
+    /// can fall outside the margin takes room from the gophers.
+    pub fn max_gophers(&self) -> usize {
+        let (width, height) = self.size();
+        let (inner_width, inner_height) = (width.saturating_sub(2 * self.gopher_margin), height.saturating_sub(2 * self.gopher_margin));
+
+        let side = 2 * self.safe_radius.unwrap_or(0) + 1;
+        let reserved_cells = side.min(inner_width) * side.min(inner_height);
+
+        (inner_width * inner_height - reserved_cells) * self.max_gophers_per_cell as usize
     }
 
     pub fn gophers(&self) -> usize {
@@ -211,7 +1899,1773 @@ impl GameConfig {
             Difficulty::Normal => NORMAL,
             Difficulty::Hard => HARD,
             Difficulty::Custom { gophers_percentage } => gophers_percentage,
+            Difficulty::Exact { gophers } => return gophers,
         } * (width * height) as f32)
             .ceil() as usize
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    TooManyCells {
+        width: usize,
+        height: usize,
+        max_cells: usize,
+    },
+    /// `capacity` counts only the cells outside the gopher margin.
+    TooManyGophers {
+        gophers: usize,
+        capacity: usize,
+    },
+    GenerationFailed {
+        attempts: usize,
+    },
+    Cancelled,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::TooManyCells { width, height, max_cells } => {
+                write!(f, "a {width}x{height} board exceeds the limit of {max_cells} cells")
+            }
+            ConfigError::TooManyGophers { gophers, capacity } => {
+                write!(f, "{gophers} gophers don't fit in the {capacity} the board can hold")
+            }
+            ConfigError::GenerationFailed { attempts } => {
+                write!(f, "no layout meeting the config was found in {attempts} attempts")
+            }
+            ConfigError::Cancelled => write!(f, "generation was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A summary of the settings that affect how hard a configuration is, suitable
+/// as a leaderboard key. Presentation-only options like fog are left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConfigKey {
+    pub width: usize,
+    pub height: usize,
+    pub gophers: usize,
+    pub max_gophers_per_cell: u8,
+    pub safe_radius: Option<usize>,
+    pub adjacency: Adjacency,
+    pub gopher_margin: usize,
+    pub anti_gophers: usize,
+    pub max_cell_number: Option<u8>,
+    pub win_condition: WinCondition,
+    pub time_limit: Option<Duration>,
+    pub cascade_reveals_borders: bool,
+    pub flagless: bool,
+}
+
+impl fmt::Display for ConfigKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}, {} gophers", self.width, self.height, self.gophers)?;
+
+        if self.max_gophers_per_cell > 1 {
+            write!(f, ", up to {} per cell", self.max_gophers_per_cell)?;
+        }
+
+        if let Some(radius) = self.safe_radius {
+            write!(f, ", safe radius {radius}")?;
+        }
+
+        if self.adjacency == Adjacency::Diagonal {
+            write!(f, ", diagonal adjacency")?;
+        }
+
+        if self.gopher_margin > 0 {
+            write!(f, ", gopher margin {}", self.gopher_margin)?;
+        }
+
+        if self.anti_gophers > 0 {
+            write!(f, ", {} anti-gophers", self.anti_gophers)?;
+        }
+
+        if let Some(max) = self.max_cell_number {
+            write!(f, ", numbers up to {max}")?;
+        }
+
+        if self.win_condition == WinCondition::FlagAllGophers {
+            write!(f, ", flag all gophers")?;
+        }
+
+        if let Some(limit) = self.time_limit {
+            write!(f, ", {}s time limit", limit.as_secs_f64())?;
+        }
+
+        if !self.cascade_reveals_borders {
+            write!(f, ", hidden cascade borders")?;
+        }
+
+        if self.flagless {
+            write!(f, ", flagless")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A board laid out from `rows`, one string per row with `*` for a gopher and
+    /// a digit for a heavy cell holding that many.
+    pub(crate) fn board(rows: &[&str]) -> GopherSweeper {
+        let (width, height) = (rows[0].len(), rows.len());
+        let mut game = GopherSweeper::new(GameConfig::new(FieldSize::Custom { width, height }, Difficulty::Custom { gophers_percentage: 0.0 }));
+
+        let mut gophers = 0;
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let count = if c == '*' { 1 } else { c.to_digit(10).unwrap_or(0) as u8 };
+
+                game.field[y][x].gopher_count = count;
+                gophers += count as usize;
+            }
+        }
+
+        game.recompute_counts();
+        set_gopher_total(&mut game, gophers);
+        game
+    }
+
+    /// Makes `config.gophers()` report `gophers` without touching the layout.
+    pub(crate) fn set_gopher_total(game: &mut GopherSweeper, gophers: usize) {
+        game.config.difficulty = Difficulty::Exact { gophers };
+    }
+
+    #[test]
+    fn only_flags_completing_an_exposed_number_are_required() {
+        let mut game = board(&["....", "****", "...."]);
+        game.try_expose_cell(0, 0);
+        game.try_expose_cell(3, 0);
+
+        game.toggle_flag(0, 1);
+        game.toggle_flag(1, 1);
+        game.toggle_flag(3, 1);
+        game.toggle_flag(3, 2);
+        assert_eq!(game.redundant_flags(), vec![(3, 1), (3, 2)]);
+
+        game.toggle_flag(1, 0);
+        assert_eq!(game.redundant_flags(), vec![(1, 0), (0, 1), (1, 1), (3, 1), (3, 2)]);
+    }
+
+    fn gophers_near(game: &GopherSweeper, x: usize, y: usize, radius: usize) -> usize {
+        let (width, height) = game.config.size();
+
+        (0..height)
+            .flat_map(|cy| (0..width).map(move |cx| (cx, cy)))
+            .filter(|&(cx, cy)| cx.abs_diff(x) <= radius && cy.abs_diff(y) <= radius && game.field[cy][cx].has_gopher())
+            .count()
+    }
+
+    #[test]
+    fn the_safe_radius_is_clear_after_the_first_click() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 8, height: 8 }, Difficulty::Custom { gophers_percentage: 0.3 }).with_safe_radius(2);
+
+        for _ in 0..20 {
+            let mut game = GopherSweeper::new(config());
+            assert_eq!(game.field.iter().flatten().filter(|cell| cell.has_gopher()).count(), 0);
+
+            game.try_expose_cell(3, 3);
+            assert_eq!(gophers_near(&game, 3, 3, 2), 0);
+            assert_eq!(game.field.iter().flatten().filter(|cell| cell.has_gopher()).count(), 20);
+        }
+    }
+
+    #[test]
+    fn the_safe_radius_shrinks_until_the_gophers_fit() {
+        let config = |radius| GameConfig::new(FieldSize::Custom { width: 5, height: 5 }, Difficulty::Custom { gophers_percentage: 0.8 }).with_safe_radius(radius);
+
+        for _ in 0..20 {
+            let mut game = GopherSweeper::new(config(2));
+            game.try_expose_cell(2, 2);
+            assert_eq!(gophers_near(&game, 2, 2, 0), 0);
+
+            let mut game = GopherSweeper::new(config(1));
+            game.try_expose_cell(0, 0);
+            assert_eq!(gophers_near(&game, 0, 0, 1), 0);
+            assert_eq!(game.field.iter().flatten().filter(|cell| cell.has_gopher()).count(), 20);
+        }
+    }
+
+    #[test]
+    fn numbers_sum_the_gophers_of_heavy_neighbors() {
+        let mut game = board(&["2..", "..*"]);
+
+        assert_eq!(game.field[0][1].surrounding_gophers, 3);
+        assert_eq!(game.field[1][0].surrounding_gophers, 2);
+        assert_eq!(game.field[0][2].surrounding_gophers, 1);
+
+        assert_eq!(game.try_expose_cell(1, 1), ExposeResult::Exposed);
+        assert_eq!(game.visible_cell(1, 1), VisibleCell::Exposed(3));
+        assert_eq!(game.try_expose_cell(0, 0), ExposeResult::HasGopher);
+    }
+
+    #[test]
+    fn generated_heavy_cells_stay_within_the_cap() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 5, height: 5 }, Difficulty::Custom { gophers_percentage: 2.0 }).with_max_gophers_per_cell(3);
+
+        for _ in 0..20 {
+            let game = GopherSweeper::new(config());
+
+            assert_eq!(game.field.iter().flatten().map(|cell| cell.gopher_count as usize).sum::<usize>(), 50);
+            assert!(game.field.iter().flatten().all(|cell| cell.gopher_count <= 3));
+
+            for y in 0..5 {
+                for x in 0..5 {
+                    let expected: u8 = game.surrounding_cells_coords(x, y).into_iter().map(|(x, y)| game.field[y][x].gopher_count).sum();
+                    assert_eq!(game.field[y][x].surrounding_gophers, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fog_obscures_cells_beyond_the_radius_of_every_exposed_cell() {
+        let mut game = board(&["..*...", "..*...", "..*..."]);
+        game.config.fog_radius = Some(1);
+        game.toggle_flag(5, 0);
+
+        assert_eq!(game.visible_cell(0, 0), VisibleCell::Obscured);
+        assert_eq!(game.visible_cell(5, 0), VisibleCell::Flagged);
+
+        game.try_expose_cell(0, 0);
+        assert_eq!(game.visible_cell(1, 1), VisibleCell::Exposed(3));
+        assert!((0..3).all(|y| game.visible_cell(2, y) == VisibleCell::Hidden));
+        assert!((0..3).all(|y| game.visible_cell(3, y) == VisibleCell::Obscured));
+        assert_eq!(game.snapshot().cell(5, 0), VisibleCell::Flagged);
+
+        game.config.fog_radius = Some(2);
+        assert_eq!(game.visible_cell(3, 2), VisibleCell::Hidden);
+        assert_eq!(game.visible_cell(4, 2), VisibleCell::Obscured);
+    }
+
+    #[test]
+    fn total_safe_cells_stays_constant_across_exposes() {
+        let mut game = board(&["2...", "....", "...*"]);
+        assert_eq!(game.total_safe_cells(), 10);
+
+        game.try_expose_cell(1, 1);
+        game.try_expose_cell(3, 0);
+
+        assert_eq!(game.total_safe_cells(), 10);
+        assert_eq!(game.remaining_cells, 4);
+    }
+
+    #[test]
+    fn total_safe_cells_counts_cells_rather_than_heavy_gophers() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 5, height: 5 }, Difficulty::Custom { gophers_percentage: 1.0 })
+            .with_max_gophers_per_cell(2)
+            .with_safe_radius(0);
+
+        for _ in 0..20 {
+            let mut game = GopherSweeper::new(config());
+            game.try_expose_cell(2, 2);
+
+            let safe = game.field.iter().flatten().filter(|cell| !cell.has_gopher()).count();
+            assert!(safe > 0);
+            assert_eq!(game.total_safe_cells(), safe);
+        }
+    }
+
+    #[test]
+    fn capped_cascades_resume_to_the_full_reveal() {
+        let rows = [".....", ".....", ".....", ".....", "....*"];
+        let mut game = board(&rows);
+
+        let (result, mut pending) = game.expose_step(0, 0, 5);
+        assert_eq!(result, ExposeResult::Exposed);
+        assert_eq!(game.remaining_cells, 19);
+
+        let mut steps = 1;
+        while !pending.is_empty() {
+            let (result, rest) = game.resume_step(pending, 5);
+            pending = rest;
+            steps += 1;
+
+            assert_eq!(result, if pending.is_empty() { ExposeResult::Win } else { ExposeResult::Exposed });
+            assert_eq!(game.remaining_cells, 24usize.saturating_sub(5 * steps));
+        }
+        assert_eq!(steps, 5);
+
+        let mut uncapped = board(&rows);
+        uncapped.try_expose_cell(0, 0);
+        assert_eq!(game.snapshot(), uncapped.snapshot());
+    }
+
+    #[test]
+    fn a_number_needs_no_resuming() {
+        let mut game = board(&["..", ".*"]);
+
+        assert_eq!(game.expose_step(0, 1, 1), (ExposeResult::Exposed, vec![]));
+        assert_eq!(game.expose_step(0, 0, 0), (ExposeResult::Exposed, vec![(0, 0)]));
+        assert!(!game.field[0][0].is_exposed);
+    }
+
+    pub(crate) fn gopher_cells(game: &GopherSweeper) -> Vec<(usize, usize)> {
+        let (width, height) = game.config.size();
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| game.field[y][x].has_gopher())
+            .collect()
+    }
+
+    #[test]
+    fn practice_boards_start_with_only_gophers_hidden() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_safe_radius(1);
+        let mut game = GopherSweeper::new_practice(config, 3);
+
+        assert!(game.field.iter().flatten().all(|cell| cell.is_exposed != cell.has_gopher()));
+        assert_eq!(gopher_cells(&game).len(), 8);
+        assert_eq!(game.remaining_cells, 0);
+
+        let safe = (0..10).find(|&x| !game.field[0][x].has_gopher()).unwrap();
+        assert_eq!(game.try_expose_cell(safe, 0), ExposeResult::WasAlreadyExposed);
+
+        let (x, y) = gopher_cells(&game)[0];
+        assert_eq!(game.try_expose_cell(x, y), ExposeResult::HasGopher);
+    }
+
+    #[test]
+    fn seeded_boards_repeat_their_layout() {
+        let config = || GameConfig::new(FieldSize::Small, Difficulty::Hard);
+        assert_eq!(gopher_cells(&GopherSweeper::new_seeded(config(), 7)), gopher_cells(&GopherSweeper::new_seeded(config(), 7)));
+        assert_ne!(gopher_cells(&GopherSweeper::new_seeded(config(), 7)), gopher_cells(&GopherSweeper::new_seeded(config(), 8)));
+
+        let deferred = || {
+            let mut game = GopherSweeper::new_seeded(config().with_safe_radius(1), 7);
+            game.try_expose_cell(4, 4);
+            gopher_cells(&game)
+        };
+        assert_eq!(deferred(), deferred());
+    }
+
+    #[test]
+    fn recompute_counts_repairs_a_hand_edited_board() {
+        let mut game = board(&["...", ".*.", "..."]);
+        game.try_expose_cell(0, 0);
+        assert_eq!(game.field[0][0].surrounding_gophers, 1);
+
+        game.field[1][1].gopher_count = 0;
+        game.field[2][2].gopher_count = 2;
+        game.recompute_counts();
+
+        let expected = board(&["...", "...", "..2"]);
+        for y in 0..3 {
+            for x in 0..3 {
+                assert_eq!(game.field[y][x].surrounding_gophers, expected.field[y][x].surrounding_gophers);
+            }
+        }
+        assert_eq!(game.field[1][1].surrounding_gophers, 2);
+        assert_eq!(game.total_safe_cells(), 8);
+        assert_eq!(game.remaining_cells, 7);
+    }
+
+    #[test]
+    fn expose_animated_reveals_outward_from_the_click() {
+        let mut game = board(&[".......", ".......", ".......", ".......", ".......", ".......", "......*"]);
+
+        let (result, order) = game.expose_animated(1, 2);
+        let distances: Vec<usize> = order.iter().map(|&(x, y)| x.abs_diff(1).max(y.abs_diff(2))).collect();
+
+        assert_eq!(result, ExposeResult::Win);
+        assert_eq!(order.len(), 48);
+        assert_eq!(order[0], (1, 2));
+        assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        assert_eq!(game.expose_animated(1, 2), (ExposeResult::GameOver, vec![]));
+    }
+
+    #[test]
+    fn an_animated_number_reveals_only_itself() {
+        let mut game = board(&["*..", "..."]);
+
+        assert_eq!(game.expose_animated(1, 1), (ExposeResult::Exposed, vec![(1, 1)]));
+    }
+
+    #[test]
+    fn max_gophers_leaves_room_for_the_safe_zone() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 5, height: 4 }, Difficulty::Easy);
+
+        assert_eq!(config().max_gophers(), 19);
+        assert_eq!(config().with_safe_radius(0).max_gophers(), 19);
+        assert_eq!(config().with_safe_radius(1).max_gophers(), 11);
+        assert_eq!(config().with_safe_radius(2).max_gophers(), 0);
+        assert_eq!(config().with_safe_radius(1).with_max_gophers_per_cell(3).max_gophers(), 33);
+    }
+
+    #[test]
+    fn max_gophers_counts_only_the_safe_zone_outside_the_margin() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 9, height: 4 }, Difficulty::Easy).with_gopher_margin(1);
+
+        assert_eq!(config().max_gophers(), 13);
+        assert_eq!(config().with_safe_radius(1).max_gophers(), 8);
+        assert_eq!(config().with_gopher_margin(2).with_safe_radius(1).max_gophers(), 0);
+
+        let full = || GameConfig::new(FieldSize::Custom { width: 9, height: 4 }, Difficulty::Exact { gophers: 8 })
+            .with_gopher_margin(1)
+            .with_safe_radius(1);
+        for seed in 0..20 {
+            let mut game = GopherSweeper::new_seeded(full(), seed);
+            game.try_expose_cell(4, 1);
+
+            assert_eq!(gopher_cells(&game).len(), 8);
+            assert!(gopher_cells(&game).iter().all(|&(x, y)| x.abs_diff(4) > 1 || y.abs_diff(1) > 1));
+        }
+    }
+
+    #[test]
+    fn a_board_at_max_gophers_keeps_the_whole_safe_zone() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 5, height: 4 }, Difficulty::Custom { gophers_percentage: 0.525 }).with_safe_radius(1);
+        assert_eq!(config().gophers(), config().max_gophers());
+
+        for _ in 0..20 {
+            let mut game = GopherSweeper::new(config());
+            game.try_expose_cell(2, 1);
+
+            assert_eq!(gopher_cells(&game).len(), 11);
+            assert_eq!(gophers_near(&game, 2, 1, 1), 0);
+        }
+    }
+
+    #[test]
+    fn reveal_policies_apply_at_the_end_of_a_game() {
+        let cases = [
+            (RevealPolicy::Leave, VisibleCell::Hidden),
+            (RevealPolicy::Flag, VisibleCell::Flagged),
+            (RevealPolicy::Reveal, VisibleCell::Gopher),
+        ];
+
+        for (policy, shown) in cases {
+            let mut won = board(&["*.*", "...", "..."]);
+            won.config.reveal_on_win = policy;
+            won.config.reveal_on_loss = RevealPolicy::Reveal;
+
+            assert_eq!(won.try_expose_cell(0, 2), ExposeResult::Exposed);
+        assert_eq!(won.visible_cell(0, 0), VisibleCell::Hidden);
+        assert_eq!(won.try_expose_cell(1, 0), ExposeResult::Win);
+            assert_eq!(won.visible_cell(0, 0), shown);
+            assert_eq!(won.visible_cell(2, 0), shown);
+
+            let mut lost = board(&["*.*", "...", "..."]);
+            lost.config.reveal_on_loss = policy;
+            lost.config.reveal_on_win = RevealPolicy::Reveal;
+            lost.toggle_flag(2, 0);
+
+            assert_eq!(lost.try_expose_cell(0, 0), ExposeResult::HasGopher);
+            assert_eq!(lost.visible_cell(0, 0), shown);
+            assert_eq!(lost.visible_cell(2, 0), if policy == RevealPolicy::Reveal { VisibleCell::Gopher } else { VisibleCell::Flagged });
+            assert_eq!(lost.visible_cell(1, 0), VisibleCell::Hidden);
+        }
+    }
+
+    #[test]
+    fn exposed_neighbor_number_sum_adds_the_visible_numbers() {
+        let mut game = board(&["..2", "...", "*.."]);
+        game.config.reveal_on_loss = RevealPolicy::Reveal;
+        assert_eq!(game.exposed_neighbor_number_sum(2, 1), 0);
+
+        game.try_expose_cell(0, 0);
+
+        assert_eq!(game.exposed_neighbor_number_sum(1, 0), 4);
+        assert_eq!(game.exposed_neighbor_number_sum(2, 1), 5);
+        assert_eq!(game.exposed_neighbor_number_sum(1, 2), 4);
+
+        assert_eq!(game.try_expose_cell(0, 2), ExposeResult::HasGopher);
+        assert!(game.field[2][0].is_exposed);
+        assert_eq!(game.exposed_neighbor_number_sum(1, 2), 4);
+    }
+
+    #[test]
+    fn cells_where_filters_in_row_major_order() {
+        let mut game = board(&["..2.", "....", "*..."]);
+        game.try_expose_cell(0, 0);
+        game.toggle_flag(2, 0);
+
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(game.cells_where(|cell| cell.is_flagged), vec![(2, 0)]);
+        assert_eq!(game.cells_where(|cell| !cell.is_exposed && !cell.is_flagged), vec![(3, 0), (2, 1), (3, 1), (0, 2), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(game.cells_where(Cell::has_gopher), vec![(2, 0), (0, 2)]);
+    }
+
+    #[test]
+    fn just_revealed_marks_the_last_action_only() {
+        let mut game = board(&["..2.", "....", "*..."]);
+        game.config.reveal_on_loss = RevealPolicy::Reveal;
+        let marked = |game: &GopherSweeper| game.cells_where(|cell| cell.just_revealed);
+
+        game.try_expose_cell(0, 0);
+        assert_eq!(marked(&game), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+
+        game.try_expose_cell(3, 0);
+        assert_eq!(marked(&game), vec![(3, 0)]);
+
+        game.toggle_flag(2, 1);
+        assert_eq!(marked(&game), vec![]);
+
+        assert_eq!(game.try_expose_cell(0, 2), ExposeResult::HasGopher);
+        assert_eq!(marked(&game), vec![(2, 0), (0, 2)]);
+
+        game.clear_transients();
+        assert_eq!(marked(&game), vec![]);
+        assert!(game.field[2][0].is_exposed);
+    }
+
+    #[test]
+    fn equivalent_configs_share_a_key() {
+        let small = || GameConfig::new(FieldSize::Small, Difficulty::Easy);
+        let (width, height) = small().size();
+        let custom = GameConfig::new(FieldSize::Custom { width, height }, Difficulty::Exact { gophers: small().gophers() })
+            .with_fog_radius(2)
+            .with_reveal_on_win(RevealPolicy::Flag)
+            .with_reveal_on_loss(RevealPolicy::Reveal);
+
+        assert_eq!(small().config_key(), custom.config_key());
+        assert_eq!(small().config_key().to_string(), "10x8, 8 gophers");
+    }
+
+    #[test]
+    fn every_setting_that_changes_play_changes_the_key() {
+        let base = || GameConfig::new(FieldSize::Small, Difficulty::Easy);
+        let variants = [
+            GameConfig::new(FieldSize::Custom { width: 8, height: 10 }, Difficulty::Easy),
+            GameConfig::new(FieldSize::Small, Difficulty::Hard),
+            base().with_safe_radius(1),
+            base().with_max_gophers_per_cell(2),
+            base().with_adjacency(Adjacency::Diagonal),
+            base().with_gopher_margin(1),
+            base().with_anti_gophers(2),
+            base().with_max_cell_number(3),
+            base().with_win_condition(WinCondition::FlagAllGophers),
+            base().with_time_limit(Duration::from_secs(60)),
+            base().with_cascade_reveals_borders(false),
+            base().with_flagless(true),
+        ];
+
+        let keys: HashSet<ConfigKey> = variants.iter().chain([&base()]).map(GameConfig::config_key).collect();
+        let labels: HashSet<String> = keys.iter().map(ConfigKey::to_string).collect();
+
+        assert_eq!(keys.len(), variants.len() + 1);
+        assert_eq!(labels.len(), keys.len());
+    }
+
+    #[test]
+    fn flags_wait_for_the_first_expose_when_asked() {
+        let config = |enabled| GameConfig::new(FieldSize::Small, Difficulty::Easy).with_safe_radius(1).with_flag_after_first_expose(enabled);
+        let mut game = GopherSweeper::new_seeded(config(true), 0);
+
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::GameNotStarted);
+        assert!(!game.field[0][0].is_flagged);
+        assert!(gopher_cells(&game).is_empty());
+
+        game.try_expose_cell(4, 4);
+
+        let (x, y) = gopher_cells(&game)[0];
+
+        assert_eq!(game.toggle_flag(4, 4), ToggleFlagResult::CellWasExposed);
+        assert_eq!(game.toggle_flag(x, y), ToggleFlagResult::Flagged);
+        assert_eq!(game.toggle_flag(x, y), ToggleFlagResult::Unflagged);
+
+        let mut game = GopherSweeper::new_seeded(config(false), 0);
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::Flagged);
+
+        let mut game = GopherSweeper::new_practice(config(true), 0);
+        let (x, y) = gopher_cells(&game)[0];
+        assert_eq!(game.toggle_flag(x, y), ToggleFlagResult::Flagged);
+    }
+
+    #[test]
+    fn diagonal_adjacency_counts_and_floods_only_diagonally() {
+        let mut game = board(&[".*.", "*.*", ".*."]);
+        assert_eq!(game.field[1][1].surrounding_gophers, 4);
+
+        game.config.adjacency = Adjacency::Diagonal;
+        game.recompute_counts();
+
+        assert_eq!(game.surrounding_cells_coords(1, 1).len(), 4);
+        assert_eq!(game.surrounding_cells_coords(0, 0), vec![(1, 1)]);
+        assert_eq!(game.field[1][1].surrounding_gophers, 0);
+        assert_eq!(game.field[0][1].surrounding_gophers, 2);
+
+        assert_eq!(game.try_expose_cell(1, 1), ExposeResult::Win);
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(0, 0), (2, 0), (1, 1), (0, 2), (2, 2)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn configs_round_trip_through_serde() {
+        let sizes = [FieldSize::Small, FieldSize::Medium, FieldSize::Big, FieldSize::Custom { width: 7, height: 5 }];
+        let difficulties = [
+            Difficulty::Easy,
+            Difficulty::Normal,
+            Difficulty::Hard,
+            Difficulty::Custom { gophers_percentage: 0.25 },
+            Difficulty::Exact { gophers: 3 },
+        ];
+
+        for size in sizes {
+            for difficulty in difficulties {
+                let config = GameConfig::new(size, difficulty).with_safe_radius(1).with_adjacency(Adjacency::Diagonal);
+                let json = serde_json::to_string(&config).unwrap();
+
+                assert_eq!(serde_json::from_str::<GameConfig>(&json).unwrap(), config);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn settings_files_use_stable_names_and_defaults() {
+        let config = GameConfig::new(FieldSize::Custom { width: 7, height: 5 }, Difficulty::Exact { gophers: 3 })
+            .with_reveal_on_loss(RevealPolicy::Flag);
+        let json = serde_json::to_value(&config).unwrap();
+
+        assert_eq!(json["field_size"], serde_json::json!({ "custom": { "width": 7, "height": 5 } }));
+        assert_eq!(json["difficulty"], serde_json::json!({ "exact": { "gophers": 3 } }));
+        assert_eq!(json["reveal_on_loss"], "flag");
+        assert_eq!(json["safe_radius"], serde_json::Value::Null);
+
+        let config: GameConfig = serde_json::from_str(r#"{"field_size": "medium", "adjacency": "diagonal"}"#).unwrap();
+        assert_eq!(config, GameConfig::new(FieldSize::Medium, Difficulty::default()).with_adjacency(Adjacency::Diagonal));
+    }
+
+    #[test]
+    fn expose_with_changes_tells_openings_from_numbers() {
+        let mut game = board(&["..2.", "....", "*..."]);
+        let report = |result, revealed: &[(usize, usize)], opened_region| ExposeReport { result, revealed: revealed.to_vec(), opened_region };
+
+        assert_eq!(game.expose_with_changes(3, 0), report(ExposeResult::Exposed, &[(3, 0)], false));
+        assert_eq!(game.expose_with_changes(0, 0), report(ExposeResult::Exposed, &[(0, 0), (1, 0), (0, 1), (1, 1)], true));
+        assert_eq!(game.expose_with_changes(0, 0), report(ExposeResult::WasAlreadyExposed, &[], false));
+        assert_eq!(game.expose_with_changes(0, 2), report(ExposeResult::HasGopher, &[], false));
+    }
+
+    #[test]
+    fn the_observer_hears_every_cell_of_a_flood() {
+        use std::sync::Mutex;
+
+        let mut game = board(&["..2.", "....", "*..."]);
+        game.config.reveal_on_loss = RevealPolicy::Flag;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        game.set_observer(Box::new(move |event| sink.lock().unwrap().push(event)));
+        let event = |x, y, kind| CellEvent { x, y, kind };
+
+        game.toggle_flag(0, 2);
+        game.try_expose_cell(0, 0);
+        game.toggle_flag(2, 1);
+        game.toggle_flag(2, 1);
+        assert_eq!(game.try_expose_cell(2, 0), ExposeResult::HasGopher);
+
+        assert_eq!(*events.lock().unwrap(), [
+            event(0, 2, CellEventKind::Flagged),
+            event(0, 0, CellEventKind::Exposed),
+            event(1, 0, CellEventKind::Exposed),
+            event(0, 1, CellEventKind::Exposed),
+            event(1, 1, CellEventKind::Exposed),
+            event(2, 1, CellEventKind::Flagged),
+            event(2, 1, CellEventKind::Unflagged),
+            event(2, 0, CellEventKind::Flagged),
+        ]);
+        assert!(game.clone().observer.0.is_none());
+    }
+
+    #[test]
+    fn correct_flags_counts_flags_rather_than_gophers() {
+        let mut game = board(&["*.2", "...", "..*"]);
+        game.config.reveal_on_loss = RevealPolicy::Flag;
+
+        game.toggle_flag(2, 0);
+        game.toggle_flag(1, 1);
+        assert_eq!(game.correct_flags(), 1);
+
+        game.toggle_flag(0, 0);
+        game.toggle_flag(0, 0);
+        assert_eq!(game.correct_flags(), 1);
+
+        assert_eq!(game.try_expose_cell(2, 2), ExposeResult::HasGopher);
+        assert_eq!(game.correct_flags(), 3);
+    }
+
+    #[test]
+    fn daily_boards_seed_from_the_padded_date() {
+        let config = || GameConfig::new(FieldSize::Small, Difficulty::Easy);
+        let daily = GopherSweeper::daily(config(), 2024, 3, 9);
+
+        assert_eq!(daily.seed, Some(0xd7917d89c2c16063));
+        assert_eq!(gopher_cells(&daily), gopher_cells(&GopherSweeper::new_seeded(config(), 0xd7917d89c2c16063)));
+        assert_eq!(gopher_cells(&daily), gopher_cells(&GopherSweeper::daily(config(), 2024, 3, 9)));
+
+        assert_ne!(GopherSweeper::daily(config(), 2024, 1, 19).seed, GopherSweeper::daily(config(), 2024, 11, 9).seed);
+        assert_ne!(gopher_cells(&daily), gopher_cells(&GopherSweeper::daily(config(), 2024, 3, 10)));
+    }
+
+    #[test]
+    fn cascades_can_stop_short_of_the_bordering_numbers() {
+        let mut game = board(&["*...", "....", "...."]);
+        assert_eq!(game.try_expose_cell(3, 2), ExposeResult::Win);
+
+        let mut game = board(&["*...", "....", "...."]);
+        game.config.cascade_reveals_borders = false;
+
+        assert_eq!(game.try_expose_cell(3, 2), ExposeResult::Exposed);
+        assert_eq!(game.cells_where(|cell| !cell.is_exposed), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(game.remaining_cells, 3);
+
+        assert_eq!(game.expose_with_changes(1, 1).revealed, vec![(1, 1)]);
+        game.try_expose_cell(1, 0);
+        assert_eq!(game.try_expose_cell(0, 1), ExposeResult::Win);
+    }
+
+    #[test]
+    fn gophers_stay_out_of_the_margin() {
+        let config = |gophers| GameConfig::new(FieldSize::Custom { width: 8, height: 8 }, Difficulty::Exact { gophers })
+            .with_safe_radius(1)
+            .with_gopher_margin(2);
+        let inside = |margin: usize| move |&(x, y): &(usize, usize)| (margin..8 - margin).contains(&x) && (margin..8 - margin).contains(&y);
+
+        for seed in 0..10 {
+            let mut game = GopherSweeper::new_seeded(config(10), seed);
+            game.try_expose_cell(0, 0);
+            assert_eq!(gopher_cells(&game).len(), 10);
+            assert!(gopher_cells(&game).iter().all(inside(2)));
+
+            let mut game = GopherSweeper::new_seeded(config(10), seed);
+            assert_ne!(game.try_expose_cell(4, 4), ExposeResult::HasGopher);
+            assert_eq!(gopher_cells(&game).len(), 10);
+            assert!(gopher_cells(&game).iter().all(inside(2)));
+
+            let mut game = GopherSweeper::new_seeded(config(20), seed);
+            game.try_expose_cell(0, 0);
+            assert_eq!(gopher_cells(&game).len(), 16);
+            assert!(gopher_cells(&game).iter().all(inside(2)));
+        }
+    }
+
+    #[test]
+    fn reset_flags_keeps_exposures_and_the_layout() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 2);
+        let exposed = game.cells_where(|cell| cell.is_exposed);
+
+        game.toggle_flag(0, 0);
+        game.toggle_flag(1, 0);
+        assert_eq!(game.flags_placed(), 2);
+
+        game.reset_flags();
+
+        assert_eq!(game.flags_placed(), 0);
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), exposed);
+        assert_eq!(game.cells_where(|cell| cell.just_revealed), vec![]);
+        assert_eq!(gopher_cells(&game), vec![(0, 0), (2, 0)]);
+        assert_eq!(game.remaining_cells, 1);
+        assert_eq!(game.try_expose_cell(1, 0), ExposeResult::Win);
+    }
+
+    #[test]
+    fn overlapping_exposes_keep_remaining_cells_correct() {
+        let mut game = board(&["....", "....", "...*"]);
+        game.config.reveal_on_win = RevealPolicy::Reveal;
+        let unexposed_safe = |game: &GopherSweeper| game.cells_where(|cell| !cell.is_exposed && !cell.has_gopher()).len();
+
+        let (_, pending) = game.expose_step(0, 0, 3);
+        let (_, rest) = game.resume_step([pending.clone(), pending.clone()].concat(), 2);
+        assert_eq!(game.remaining_cells, unexposed_safe(&game));
+
+        let overlapping = [rest.clone(), pending, vec![(0, 0)]].concat();
+        assert_eq!(game.resume_step(overlapping, usize::MAX), (ExposeResult::Win, vec![]));
+        assert_eq!(game.resume_step(rest, usize::MAX).1, vec![]);
+
+        assert_eq!(game.remaining_cells, 0);
+        assert!(game.field[2][3].is_exposed);
+    }
+
+    #[test]
+    fn try_new_rejects_boards_over_max_cells() {
+        let config = |width, height| GameConfig::new(FieldSize::Custom { width, height }, Difficulty::Easy).with_max_cells(12);
+
+        assert!(GopherSweeper::try_new(config(4, 3)).is_ok());
+        assert_eq!(
+            GopherSweeper::try_new_seeded(config(5, 3), 0).err(),
+            Some(ConfigError::TooManyCells { width: 5, height: 3, max_cells: 12 }),
+        );
+        assert_eq!(
+            config(5, 3).validate().unwrap_err().to_string(),
+            "a 5x3 board exceeds the limit of 12 cells",
+        );
+
+        let overflowing = GameConfig::new(FieldSize::Custom { width: usize::MAX, height: 2 }, Difficulty::Easy).with_max_cells(usize::MAX);
+        assert!(GopherSweeper::try_new(overflowing).is_err());
+        assert!(GopherSweeper::try_new(GameConfig::new(FieldSize::Custom { width: 5000, height: 5000 }, Difficulty::Easy)).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn max_cells_is_not_read_from_settings_files() {
+        let json = serde_json::to_string(&GameConfig::default().with_max_cells(4)).unwrap();
+
+        assert!(!json.contains("max_cells"));
+        assert_eq!(serde_json::from_str::<GameConfig>(&json).unwrap().max_cells(), DEFAULT_MAX_CELLS);
+    }
+
+    #[test]
+    fn region_borders_ignore_lone_numbers() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 0);
+
+        assert_eq!(game.region_borders(), vec![]);
+        assert!(!game.frontier().unexposed.is_empty());
+
+        game.try_expose_cell(1, 2);
+        assert_eq!(game.region_borders(), vec![(0, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn region_borders_follow_the_numbers_around_an_opening() {
+        let mut game = board(&["...*", "....", "*..."]);
+        game.toggle_flag(3, 0);
+        game.try_expose_cell(0, 0);
+
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+        assert_eq!(game.region_borders(), vec![(3, 0), (3, 1), (0, 2), (1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn chords_need_their_number_satisfied() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 2);
+
+        assert_eq!(game.chord(0, 1), None);
+        assert_eq!(game.chord(1, 0), None);
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.chord(0, 1), Some(ExposeResult::Win));
+        assert_eq!(game.chord(0, 1), None);
+    }
+
+    #[test]
+    fn chords_on_a_wrong_flag_hit_the_gopher() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.config.reveal_on_loss = RevealPolicy::Reveal;
+        game.try_expose_cell(1, 2);
+        game.toggle_flag(1, 0);
+
+        assert_eq!(game.chord(0, 1), Some(ExposeResult::HasGopher));
+        assert_eq!(game.visible_cell(0, 0), VisibleCell::Gopher);
+        assert_eq!(game.visible_cell(1, 0), VisibleCell::Flagged);
+    }
+
+    #[test]
+    fn flagless_chords_count_deduced_gophers_as_flags() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 2);
+        assert_eq!(game.chord(0, 1), None);
+
+        game.config.flagless = true;
+        assert_eq!(game.chord(2, 1), Some(ExposeResult::Win));
+        assert_eq!(game.flags_placed(), 0);
+    }
+
+    #[test]
+    fn same_layout_ignores_play_progress() {
+        let mut played = board(&["*..", "..2"]);
+        played.try_expose_cell(0, 1);
+        played.toggle_flag(0, 0);
+        played.config.fog_radius = Some(1);
+
+        assert!(played.same_layout(&board(&["*..", "..2"])));
+        assert!(!played.same_layout(&board(&["*..", "..*"])));
+        assert!(!played.same_layout(&board(&["*.", "..", ".2"])));
+    }
+
+    #[test]
+    fn expose_row_reveals_a_gopher_free_row() {
+        let mut game = board(&["*..*", "....", "...."]);
+
+        assert_eq!(game.expose_row(2, false), ExposeResult::Exposed);
+        assert_eq!(game.remaining_cells, 2);
+        assert_eq!(game.cells_where(|cell| !cell.is_exposed), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn expose_row_with_a_gopher_loses_unless_skipping() {
+        let mut game = board(&["*..*", "....", "...."]);
+        assert_eq!(game.expose_row(0, false), ExposeResult::HasGopher);
+        assert_eq!(game.remaining_cells, 10);
+
+        let mut game = board(&["*..*", "....", "...."]);
+        assert_eq!(game.expose_column(3, true), ExposeResult::Exposed);
+        assert_eq!(game.remaining_cells, 2);
+        assert!(!game.field[0][3].is_exposed);
+
+        assert_eq!(game.expose_row(0, true), ExposeResult::Win);
+        assert_eq!(game.cells_where(|cell| !cell.is_exposed), vec![(0, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn danger_heatmap_is_highest_next_to_high_numbers() {
+        let mut game = board(&["*.*", "...", "..."]);
+        assert_eq!(game.danger_heatmap(), vec![vec![0.0; 3]; 3]);
+
+        game.try_expose_cell(1, 2);
+        game.toggle_flag(0, 0);
+
+        assert_eq!(game.danger_heatmap(), vec![vec![0.75, 1.0, 0.75], vec![0.0; 3], vec![0.0; 3]]);
+    }
+
+    #[test]
+    fn anti_gophers_lower_neighbor_counts_down_to_zero() {
+        let mut game = board(&["2..", "...", "*.."]);
+        game.field[1][1].kind = CellKind::AntiGopher;
+        game.field[2][1].kind = CellKind::AntiGopher;
+        game.recompute_counts();
+
+        assert_eq!(game.field[0][1].surrounding_gophers, 1);
+        assert_eq!(game.field[1][0].surrounding_gophers, 1);
+        assert_eq!(game.field[2][2].surrounding_gophers, 0);
+        assert_eq!(game.field[1][2].surrounding_gophers, 0);
+        assert_eq!(game.field[1][1].surrounding_gophers, 2);
+    }
+
+    #[test]
+    fn generated_anti_gophers_sit_on_gopher_free_cells() {
+        let config = GameConfig::new(FieldSize::Custom { width: 4, height: 4 }, Difficulty::Exact { gophers: 6 }).with_anti_gophers(20);
+
+        for seed in 0..10 {
+            let game = GopherSweeper::new_seeded(config.clone(), seed);
+            let anti_gophers = game.cells_where(|cell| cell.kind == CellKind::AntiGopher);
+
+            assert_eq!(anti_gophers.len(), 10);
+            assert!(anti_gophers.iter().all(|&(x, y)| !game.field[y][x].has_gopher()));
+        }
+    }
+
+    #[test]
+    fn neighbor_offsets_match_each_adjacency() {
+        let standard = GopherSweeper::neighbor_offsets(Adjacency::Standard);
+        let diagonal = GopherSweeper::neighbor_offsets(Adjacency::Diagonal);
+
+        assert_eq!(standard.iter().collect::<HashSet<_>>().len(), 8);
+        assert!(standard.iter().all(|&(dx, dy)| (dx, dy) != (0, 0) && dx.abs() <= 1 && dy.abs() <= 1));
+        assert_eq!(diagonal, [(-1, -1), (-1, 1), (1, -1), (1, 1)]);
+
+        let mut game = board(&["...", "..."]);
+        assert_eq!(game.surrounding_cells_coords(0, 0), vec![(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(game.surrounding_cells_coords(2, 1), vec![(1, 1), (2, 0), (1, 0)]);
+
+        game.config.adjacency = Adjacency::Diagonal;
+        assert_eq!(game.surrounding_cells_coords(1, 0), vec![(0, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn keyed_boards_depend_only_on_the_key() {
+        let config = || GameConfig::new(FieldSize::Small, Difficulty::Easy);
+
+        assert_eq!(GopherSweeper::new_from_key(config(), b"").seed, Some(0xcbf29ce484222325));
+        assert_eq!(GopherSweeper::new_from_key(config(), b"a").seed, Some(0xaf63dc4c8601ec8c));
+        assert!(GopherSweeper::new_from_key(config(), b"2024-03-09").same_layout(&GopherSweeper::daily(config(), 2024, 3, 9)));
+        assert!(!GopherSweeper::new_from_key(config(), b"room-1").same_layout(&GopherSweeper::new_from_key(config(), b"room-2")));
+
+        let mut first = GopherSweeper::new_from_key(config().with_safe_radius(1), b"room-1");
+        let mut second = GopherSweeper::new_from_key(config().with_safe_radius(1), b"room-1");
+        first.try_expose_cell(3, 3);
+        second.try_expose_cell(3, 3);
+        assert!(first.same_layout(&second));
+    }
+
+    #[test]
+    fn exposed_count_includes_revealed_gophers() {
+        let mut game = board(&["*..*", "....", "...."]);
+        game.config.reveal_on_loss = RevealPolicy::Reveal;
+        let scanned = |game: &GopherSweeper| game.cells_where(|cell| cell.is_exposed).len();
+
+        let revealed = game.expose_with_changes(0, 2).revealed;
+        assert_eq!(game.exposed_count(), revealed.len());
+        assert_eq!(game.exposed_count(), 8);
+
+        game.rotate_cw();
+        assert_eq!(game.exposed_count(), scanned(&game));
+
+        game.try_expose_cell(2, 1);
+        assert_eq!(game.exposed_count(), 9);
+
+        assert_eq!(game.try_expose_cell(2, 0), ExposeResult::HasGopher);
+        assert_eq!(game.exposed_count(), 11);
+        assert_eq!(game.exposed_count(), scanned(&game));
+
+        let practice = GopherSweeper::new_practice(GameConfig::new(FieldSize::Small, Difficulty::Easy), 0);
+        assert_eq!(practice.exposed_count(), practice.total_safe_cells());
+    }
+
+    #[test]
+    fn flagging_a_satisfied_number_chords_it_when_enabled() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 2);
+        game.toggle_flag(0, 0);
+        assert_eq!(game.toggle_flag(0, 1), ToggleFlagResult::CellWasExposed);
+
+        game.config.exposed_flag_chords = true;
+        assert_eq!(game.toggle_flag(1, 1), ToggleFlagResult::CellWasExposed);
+        assert_eq!(game.toggle_flag(0, 1), ToggleFlagResult::Chorded(ExposeResult::Win));
+        assert_eq!(game.toggle_flag(0, 1), ToggleFlagResult::GameOver);
+
+        let mut game = board(&["*.*", "...", "..."]);
+        game.config.exposed_flag_chords = true;
+        game.try_expose_cell(1, 2);
+        game.toggle_flag(1, 0);
+        assert_eq!(game.toggle_flag(2, 1), ToggleFlagResult::Chorded(ExposeResult::HasGopher));
+    }
+
+    #[test]
+    fn seeded_layout_is_pinned() {
+        let game = GopherSweeper::new_seeded(GameConfig::new(FieldSize::Small, Difficulty::Easy), 42);
+
+        assert_eq!(
+            game.cells_where(Cell::has_gopher),
+            vec![(2, 1), (5, 1), (7, 1), (9, 4), (4, 5), (3, 6), (5, 7), (6, 7)],
+        );
+    }
+
+    #[test]
+    fn deferred_seeded_layouts_are_pinned() {
+        let config = GameConfig::new(FieldSize::Custom { width: 6, height: 5 }, Difficulty::Exact { gophers: 5 })
+            .with_safe_radius(1)
+            .with_anti_gophers(2);
+        let mut game = GopherSweeper::new_seeded(config, 7);
+        game.try_expose_cell(0, 0);
+
+        assert_eq!(game.cells_where(Cell::has_gopher), vec![(3, 1), (2, 2), (4, 3), (0, 4), (2, 4)]);
+        assert_eq!(game.cells_where(|cell| cell.kind == CellKind::AntiGopher), vec![(1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn chord_would_lose_spots_a_misplaced_flag() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 2);
+        assert!(!game.chord_would_lose(0, 1));
+
+        game.toggle_flag(1, 0);
+        assert!(game.chord_would_lose(0, 1));
+        assert!(game.chord_would_lose(2, 1));
+        assert!(!game.chord_would_lose(1, 1));
+        assert_eq!(game.remaining_cells, 1);
+
+        game.toggle_flag(1, 0);
+        game.toggle_flag(0, 0);
+        assert!(!game.chord_would_lose(0, 1));
+        assert_eq!(game.chord(0, 1), Some(ExposeResult::Win));
+    }
+
+    #[test]
+    fn batches_match_individual_seeded_boards() {
+        let config = GameConfig::new(FieldSize::Medium, Difficulty::Hard);
+        let seeds: Vec<u64> = (0..32).rev().chain([5, 5]).collect();
+        let batch = GopherSweeper::generate_batch(&config, &seeds);
+
+        assert_eq!(batch.len(), seeds.len());
+
+        for (game, &seed) in batch.iter().zip(&seeds) {
+            assert_eq!(game.seed, Some(seed));
+            assert!(game.same_layout(&GopherSweeper::new_seeded(config.clone(), seed)));
+        }
+
+        assert!(GopherSweeper::generate_batch(&config, &[]).is_empty());
+
+        let deferred = GopherSweeper::generate_batch(&config.with_safe_radius(1), &[1, 2]);
+        assert!(deferred.iter().all(|game| !game.generated && gopher_cells(game).is_empty()));
+    }
+
+    #[test]
+    fn editor_overlay_shows_every_number_but_gophers() {
+        let mut game = board(&["*..", "..2"]);
+        game.field[0][1].kind = CellKind::AntiGopher;
+        game.recompute_counts();
+        game.toggle_flag(0, 1);
+        let overlay = game.editor_overlay();
+
+        assert_eq!(overlay, vec![vec![None, Some(3), Some(1)], vec![Some(0), Some(2), None]]);
+        assert!(game.cells_where(|cell| cell.is_exposed).is_empty());
+    }
+
+    #[test]
+    fn the_time_limit_loses_only_a_running_game() {
+        let limit = Duration::from_secs(30);
+        let start = Instant::now();
+
+        let mut game = board(&["*.", ".."]);
+        game.config = game.config.clone().with_time_limit(limit);
+        assert_eq!(game.tick(start + limit * 2), GameState::Playing);
+
+        game.try_expose_cell(1, 1);
+        assert_eq!(game.tick(start), GameState::Playing);
+        assert_eq!(game.tick(start + limit - Duration::from_secs(1)), GameState::Playing);
+        assert_eq!(game.tick(start + limit), GameState::Lost(LossReason::TimeExpired));
+
+        let mut lost = board(&["*.", ".."]);
+        lost.config = lost.config.clone().with_time_limit(limit);
+        lost.try_expose_cell(1, 1);
+        lost.tick(start);
+        lost.try_expose_cell(0, 0);
+        assert_eq!(lost.tick(start + limit), GameState::Lost(LossReason::HitGopher));
+
+        let mut won = board(&["*..", "...", "..."]);
+        won.config = won.config.clone().with_time_limit(limit);
+        assert_eq!(won.try_expose_cell(2, 2), ExposeResult::Win);
+        won.tick(start);
+        assert_eq!(won.tick(start + limit), GameState::Won);
+    }
+
+    #[test]
+    fn flags_match_gophers_only_for_the_exact_set() {
+        let mut game = board(&["2..", "..*"]);
+        game.field[0][2].kind = CellKind::AntiGopher;
+        game.recompute_counts();
+
+        game.toggle_flag(0, 0);
+        assert!(!game.flags_match_gophers());
+
+        game.toggle_flag(2, 1);
+        assert!(game.flags_match_gophers());
+
+        game.toggle_flag(2, 0);
+        assert!(!game.flags_match_gophers());
+
+        let deferred = GopherSweeper::new(GameConfig::new(FieldSize::Small, Difficulty::Easy).with_safe_radius(1));
+        assert!(!deferred.flags_match_gophers());
+    }
+
+    fn flag_all_gophers(rows: &[&str]) -> GopherSweeper {
+        let mut game = board(rows);
+        game.config = game.config.clone().with_win_condition(WinCondition::FlagAllGophers);
+        game
+    }
+
+    #[test]
+    fn flagging_exactly_the_gophers_wins() {
+        let mut game = flag_all_gophers(&["2..", "..*"]);
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::Flagged);
+        assert_eq!(game.toggle_flag(1, 0), ToggleFlagResult::Flagged);
+        assert_eq!(game.toggle_flag(1, 0), ToggleFlagResult::Unflagged);
+        assert_eq!(game.toggle_flag(2, 1), ToggleFlagResult::Win);
+        assert_eq!(game.state(), GameState::Won);
+        assert_eq!(game.exposed_cells, 0);
+    }
+
+    #[test]
+    fn clearing_the_safe_cells_does_not_win_when_flagging_is_required() {
+        let mut game = flag_all_gophers(&["*..", "...", "..."]);
+        assert_eq!(game.try_expose_cell(2, 2), ExposeResult::Exposed);
+        assert_eq!(game.remaining_cells, 0);
+        assert_eq!(game.state(), GameState::Playing);
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::Win);
+    }
+
+    #[test]
+    fn flagging_the_gophers_after_a_loss_does_not_win() {
+        let mut game = flag_all_gophers(&["*.*", "...", "..."]);
+        game.toggle_flag(0, 0);
+        assert_eq!(game.try_expose_cell(2, 0), ExposeResult::HasGopher);
+
+        assert_eq!(game.toggle_flag(2, 0), ToggleFlagResult::GameOver);
+        assert_eq!(game.state(), GameState::Lost(LossReason::HitGopher));
+    }
+
+    #[test]
+    fn surrounding_gophers_is_none_off_the_board() {
+        let game = board(&["*..", "..2"]);
+
+        assert_eq!(game.surrounding_gophers(1, 0), Some(3));
+        assert_eq!(game.surrounding_gophers(2, 1), Some(0));
+        assert!(game.in_bounds(2, 1));
+        assert!(!game.in_bounds(3, 0) && !game.in_bounds(0, 2));
+        assert_eq!(game.surrounding_gophers(3, 0), None);
+        assert_eq!(game.surrounding_gophers(0, 2), None);
+        assert_eq!(game.surrounding_gophers(usize::MAX, 0), None);
+    }
+
+    #[test]
+    fn lines_walk_every_direction() {
+        assert_eq!(line_cells((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(line_cells((1, 3), (1, 0)), vec![(1, 3), (1, 2), (1, 1), (1, 0)]);
+        assert_eq!(line_cells((2, 2), (0, 0)), vec![(2, 2), (1, 1), (0, 0)]);
+        assert_eq!(line_cells((0, 0), (4, 1)), vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!(line_cells((1, 1), (1, 1)), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn expose_line_skips_flags_and_stops_at_the_first_gopher() {
+        let mut game = board(&["*.*.", "....", "*..."]);
+        game.toggle_flag(1, 1);
+
+        assert_eq!(
+            game.expose_line((3, 1), (0, 1)),
+            vec![ExposeResult::Exposed, ExposeResult::Exposed, ExposeResult::IsFlagged, ExposeResult::Exposed],
+        );
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(0, 1), (2, 1), (3, 1)]);
+
+        assert_eq!(game.expose_line((1, 0), (3, 0)), vec![ExposeResult::Exposed, ExposeResult::HasGopher]);
+        assert!(!game.cell(3, 0).is_exposed);
+    }
+
+    #[test]
+    fn layout_hash_covers_the_shape_and_every_count() {
+        let mut game = board(&["*..", "..2"]);
+        let hash = game.layout_hash();
+
+        let mut bytes = [3u64.to_le_bytes(), 2u64.to_le_bytes()].concat();
+        bytes.extend([1, 0, 0, 0, 0, 2]);
+        assert_eq!(hash, fnv1a(&bytes));
+
+        game.toggle_flag(0, 0);
+        game.try_expose_cell(1, 0);
+        assert_eq!(game.layout_hash(), hash);
+
+        assert_ne!(board(&["*.", "..", ".2"]).layout_hash(), board(&["*..", "..2"]).layout_hash());
+        assert_ne!(board(&["*..", "..*"]).layout_hash(), hash);
+        assert_ne!(board(&["...", "..2"]).layout_hash(), hash);
+    }
+
+    fn assisted(rows: &[&str]) -> GopherSweeper {
+        let mut game = board(rows);
+        game.config = game.config.clone().with_assist_on_correct_flag(true);
+        game
+    }
+
+    #[test]
+    fn correct_flags_chord_their_satisfied_neighbors_in_assist_mode() {
+        let mut game = assisted(&["*..*", "....", "...*"]);
+        game.try_expose_cell(1, 1);
+        assert_eq!(game.toggle_flag(1, 0), ToggleFlagResult::Flagged);
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(1, 1)]);
+
+        game.toggle_flag(1, 0);
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::Flagged);
+        assert_eq!(
+            game.cells_where(|cell| cell.is_exposed),
+            vec![(1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)],
+        );
+        assert_eq!(game.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn the_assist_never_chords_over_a_misplaced_flag() {
+        let mut game = assisted(&["*..*", "....", "...*"]);
+        game.try_expose_cell(1, 1);
+        game.try_expose_cell(2, 1);
+
+        game.toggle_flag(3, 1);
+        assert_eq!(game.toggle_flag(3, 0), ToggleFlagResult::Flagged);
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(1, 1), (2, 1)]);
+        assert_eq!(game.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn an_assist_chord_that_clears_the_board_wins() {
+        let mut game = assisted(&["*..", "...", "..."]);
+        game.try_expose_cell(1, 1);
+
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::Win);
+        assert_eq!(game.state(), GameState::Won);
+        assert_eq!(game.remaining_cells, 0);
+    }
+
+    #[test]
+    fn campaign_levels_get_steadily_harder() {
+        let base = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_safe_radius(1);
+        let levels = GameConfig::campaign(base.clone(), 5);
+
+        assert_eq!(levels.len(), 5);
+        assert_eq!(levels[0].size(), base.size());
+        assert_eq!(levels[0].gophers(), base.gophers());
+        assert_eq!(levels[4].size(), (15, 12));
+        assert_eq!(levels[4].gophers(), 45);
+        assert!(levels.windows(2).all(|pair| pair[0].gophers() < pair[1].gophers()));
+        assert!(levels.windows(2).all(|pair| pair[0].size().0 <= pair[1].size().0 && pair[0].size().1 <= pair[1].size().1));
+        assert!(levels.iter().all(|config| config.safe_radius() == Some(1)));
+
+        assert_eq!(GameConfig::campaign(base.clone(), 1)[0].size(), base.size());
+        assert!(GameConfig::campaign(base, 0).is_empty());
+    }
+
+    #[test]
+    fn dense_campaigns_keep_their_density() {
+        let base = GameConfig::new(FieldSize::Custom { width: 10, height: 10 }, Difficulty::Exact { gophers: 40 });
+        let last = GameConfig::campaign(base, 3).pop().unwrap();
+
+        assert_eq!(last.size(), (15, 15));
+        assert_eq!(last.gophers(), 90);
+    }
+
+    #[test]
+    fn notes_can_be_set_read_and_cleared() {
+        let mut game = board(&["*..", "..2"]);
+        let hash = game.layout_hash();
+        let note = CellNote { text: "1 or 2?".to_string(), candidates: BTreeSet::from([1, 2]) };
+
+        assert_eq!(game.note(1, 0), &CellNote::default());
+        game.set_note(1, 0, note.clone());
+        game.try_expose_cell(1, 0);
+        assert_eq!(game.note(1, 0), &note);
+        assert_eq!(game.note(2, 0), &CellNote::default());
+        assert_eq!(game.layout_hash(), hash);
+
+        game.set_note(1, 0, CellNote::default());
+        assert!(game.cell(1, 0).note.is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn notes_round_trip_through_serde() {
+        let note = CellNote { text: String::new(), candidates: BTreeSet::from([3]) };
+        let json = serde_json::to_string(&note).unwrap();
+
+        assert_eq!(json, r#"{"text":"","candidates":[3]}"#);
+        assert_eq!(serde_json::from_str::<CellNote>(r#"{"candidates":[3]}"#).unwrap(), note);
+    }
+
+    #[test]
+    fn max_cell_number_caps_every_number() {
+        let config = GameConfig::new(FieldSize::Custom { width: 12, height: 12 }, Difficulty::Exact { gophers: 36 })
+            .with_max_gophers_per_cell(2)
+            .with_max_cell_number(3);
+
+        for seed in 0..10 {
+            let game = GopherSweeper::try_new_seeded(config.clone(), seed).unwrap();
+
+            assert_eq!(game.field.iter().flatten().map(|cell| cell.gopher_count as usize).sum::<usize>(), 36);
+            assert!(game.field.iter().flatten().all(|cell| cell.has_gopher() || cell.surrounding_gophers <= 3));
+        }
+    }
+
+    #[test]
+    fn unreachable_number_caps_fail_generation() {
+        let config = GameConfig::new(FieldSize::Custom { width: 4, height: 4 }, Difficulty::Exact { gophers: 8 }).with_max_cell_number(0);
+
+        assert_eq!(GopherSweeper::try_new(config.clone()).err(), Some(ConfigError::GenerationFailed { attempts: MAX_GENERATION_ATTEMPTS }));
+        assert_eq!(GopherSweeper::try_new_seeded(config.clone(), 3).err(), Some(ConfigError::GenerationFailed { attempts: MAX_GENERATION_ATTEMPTS }));
+        assert_eq!(GopherSweeper::new(config).config.gophers(), 8);
+    }
+
+    #[test]
+    fn a_set_cancel_flag_stops_generation() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_max_cell_number(4);
+        let cancel = Arc::new(AtomicBool::new(true));
+
+        let result = GopherSweeper::try_new_cancellable(config.clone(), Some(5), Arc::clone(&cancel));
+        assert_eq!(result.err(), Some(ConfigError::Cancelled));
+
+        cancel.store(false, Ordering::Relaxed);
+        let game = GopherSweeper::try_new_cancellable(config.clone(), Some(5), cancel).unwrap();
+        assert!(game.same_layout(&GopherSweeper::try_new_seeded(config, 5).unwrap()));
+    }
+
+    #[test]
+    fn changed_since_lists_cells_that_look_different() {
+        let mut game = board(&["*..", "..2"]);
+        let mut snapshot = game.snapshot();
+        assert!(game.changed_since(&snapshot).is_empty());
+
+        game.toggle_flag(0, 0);
+        game.try_expose_cell(1, 0);
+        game.toggle_flag(2, 1);
+        game.toggle_flag(2, 1);
+        assert_eq!(game.changed_since(&snapshot), vec![(0, 0), (1, 0)]);
+
+        snapshot.cells.pop();
+        assert_eq!(game.changed_since(&snapshot), vec![(0, 0), (1, 0), (0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn exposing_a_satisfied_number_again_chords_it_when_enabled() {
+        let started = |enabled| {
+            let mut game = board(&["*..*", "....", "...*"]);
+            game.config = game.config.clone().with_double_click_chords(enabled);
+            game.try_expose_cell(1, 1);
+            game
+        };
+
+        let mut plain = started(false);
+        plain.toggle_flag(0, 0);
+        assert_eq!(plain.try_expose_cell(1, 1), ExposeResult::WasAlreadyExposed);
+        assert_eq!(plain.cells_where(|cell| cell.is_exposed), vec![(1, 1)]);
+
+        let mut game = started(true);
+        assert_eq!(game.try_expose_cell(1, 1), ExposeResult::WasAlreadyExposed);
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.try_expose_cell(1, 1), ExposeResult::Exposed);
+        assert_eq!(
+            game.cells_where(|cell| cell.is_exposed),
+            vec![(1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)],
+        );
+    }
+
+    #[test]
+    fn gopher_variants_follow_their_weights() {
+        let config = GameConfig::new(FieldSize::Custom { width: 40, height: 40 }, Difficulty::Exact { gophers: 400 });
+        let game = GopherSweeper::new_seeded(config.clone().with_gopher_variant_weights(vec![0, 3, 1]), 9);
+
+        let variants: Vec<u8> = gopher_cells(&game).into_iter().map(|(x, y)| game.gopher_variant(x, y).unwrap()).collect();
+        let common = variants.iter().filter(|&&variant| variant == 1).count();
+        assert!(variants.iter().all(|&variant| variant == 1 || variant == 2));
+        assert!((250..350).contains(&common), "{common} of 400 gophers got the common variant");
+
+        let (x, y) = game.cells_where(|cell| !cell.has_gopher())[0];
+        assert_eq!(game.gopher_variant(x, y), None);
+        assert_eq!(game.gopher_variant(40, 0), None);
+        assert!(game.same_layout(&GopherSweeper::new_seeded(config.clone(), 9)));
+
+        let single = GopherSweeper::new_seeded(config.with_gopher_variant_weights(vec![5]), 9);
+        assert!(single.field.iter().flatten().all(|cell| cell.variant == 0));
+    }
+
+    #[test]
+    fn exposing_a_protected_cell_does_nothing() {
+        let mut game = board(&["*..", "...", "..."]);
+        game.set_protected(2, 0, true);
+        game.set_protected(0, 0, true);
+
+        assert_eq!(game.try_expose_cell(2, 0), ExposeResult::Protected);
+        assert_eq!(game.try_expose_cell(0, 0), ExposeResult::Protected);
+        assert_eq!(game.state(), GameState::Playing);
+
+        assert_eq!(game.try_expose_cell(2, 2), ExposeResult::Exposed);
+        assert!(game.cell(1, 0).is_exposed && !game.cell(2, 0).is_exposed);
+        assert_eq!(game.remaining_cells, 1);
+
+        game.set_protected(2, 0, false);
+        assert_eq!(game.try_expose_cell(2, 0), ExposeResult::Win);
+    }
+
+    #[test]
+    fn chords_pass_protected_cells_by() {
+        let mut game = board(&["*..*", "....", "...*"]);
+        game.try_expose_cell(1, 1);
+        game.set_protected(0, 2, true);
+        game.toggle_flag(0, 0);
+
+        assert_eq!(game.chord(1, 1), Some(ExposeResult::Exposed));
+        assert!(!game.cell(0, 2).is_exposed);
+        assert!(game.cell(0, 1).is_exposed && game.cell(1, 2).is_exposed);
+    }
+
+    #[test]
+    fn rings_are_clipped_to_the_board() {
+        let game = GopherSweeper::new(GameConfig::new(FieldSize::Custom { width: 5, height: 4 }, Difficulty::Easy));
+
+        assert_eq!(game.ring(2, 2, 0), vec![(2, 2)]);
+        assert_eq!(game.ring(2, 2, 2), vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (0, 1), (4, 1), (0, 2), (4, 2), (0, 3), (4, 3)]);
+        assert_eq!(game.ring(0, 0, 1), vec![(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(game.ring(4, 3, 4), vec![(0, 0), (0, 1), (0, 2), (0, 3)]);
+        assert!(game.ring(2, 2, 5).is_empty());
+        assert!(game.ring(0, 0, usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn reveal_size_matches_what_an_expose_reveals() {
+        let exposed = |game: &GopherSweeper| game.cells_where(|cell| cell.is_exposed).len();
+
+        for borders in [true, false] {
+            let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_cascade_reveals_borders(borders);
+            let mut game = GopherSweeper::new_seeded(config, 4);
+            let opening = game.cells_where(|cell| !cell.has_gopher() && cell.surrounding_gophers == 0);
+            game.set_protected(opening[1].0, opening[1].1, true);
+
+            for (x, y) in game.cells_where(|cell| !cell.has_gopher()) {
+                let mut clicked = game.clone();
+                clicked.try_expose_cell(x, y);
+                assert_eq!(game.reveal_size(x, y), exposed(&clicked), "{x},{y}");
+            }
+
+            let (x, y) = game.cells_where(Cell::has_gopher)[0];
+            assert_eq!(game.reveal_size(x, y), 1);
+
+            game.try_expose_cell(opening[0].0, opening[0].1);
+            game.toggle_flag(x, y);
+            assert_eq!(game.reveal_size(opening[0].0, opening[0].1), 0);
+            assert_eq!(game.reveal_size(x, y), 0);
+        }
+
+        let deferred = GopherSweeper::new(GameConfig::new(FieldSize::Small, Difficulty::Easy).with_safe_radius(1));
+        assert_eq!(deferred.reveal_size(0, 0), 0);
+    }
+
+    #[test]
+    fn only_lost_games_are_unwinnable() {
+        let mut game = flag_all_gophers(&["*..", "..2"]);
+        game.toggle_flag(1, 0);
+        game.toggle_flag(1, 1);
+        assert!(game.is_winnable());
+
+        game.try_expose_cell(2, 0);
+        assert!(game.is_winnable());
+        game.try_expose_cell(0, 0);
+        assert_eq!(game.state(), GameState::Lost(LossReason::HitGopher));
+        assert!(!game.is_winnable());
+
+        let limit = Duration::from_secs(5);
+        let start = Instant::now();
+        let mut timed = board(&["*..", "..2"]);
+        timed.config = timed.config.clone().with_time_limit(limit);
+        timed.try_expose_cell(2, 0);
+        timed.tick(start);
+        assert!(timed.is_winnable());
+        timed.tick(start + limit);
+        assert!(!timed.is_winnable());
+
+        let mut won = board(&["*..", "...", "..."]);
+        won.try_expose_cell(2, 2);
+        assert!(won.is_winnable());
+    }
+
+    #[test]
+    fn revealed_sorted_lists_the_flood_row_by_row() {
+        let mut game = board(&["*..", "...", "..."]);
+        let report = game.expose_with_changes(2, 2);
+
+        assert_eq!(report.revealed[0], (2, 2));
+        assert_eq!(report.revealed_sorted(), vec![(1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_ne!(report.revealed, report.revealed_sorted());
+    }
+
+    #[test]
+    fn fair_pairs_differ_in_layout_but_not_difficulty() {
+        let config = GameConfig::new(FieldSize::Medium, Difficulty::Normal);
+
+        for seed in 0..5 {
+            let (first, second) = GopherSweeper::fair_pair(&config, seed, 0.01);
+
+            assert!(!first.same_layout(&second));
+            assert!((first.difficulty_score() - second.difficulty_score()).abs() <= 0.01);
+
+            let (again, _) = GopherSweeper::fair_pair(&config, seed, 0.01);
+            assert!(again.same_layout(&first));
+        }
+
+        let (first, second) = GopherSweeper::fair_pair(&config, 0, 0.0);
+        assert!(!first.same_layout(&second));
+    }
+
+    #[test]
+    fn the_largest_opening_is_exposed_first() {
+        let mut game = board(&["..*....", "..*....", "..*...."]);
+
+        assert_eq!(game.expose_largest_opening(), ExposeResult::Exposed);
+        assert!(game.cell(6, 0).is_exposed && game.cell(3, 2).is_exposed);
+        assert!(!game.cell(0, 0).is_exposed);
+
+        assert_eq!(game.expose_largest_opening(), ExposeResult::Win);
+        assert_eq!(game.expose_largest_opening(), ExposeResult::WasAlreadyExposed);
+    }
+
+    #[test]
+    fn a_flagged_empty_cell_stops_the_cascade() {
+        let mut game = board(&["......", "......", ".....*"]);
+        for y in 0..3 {
+            game.toggle_flag(2, y);
+        }
+        assert_eq!(game.reveal_size(0, 0), 6);
+
+        assert_eq!(game.try_expose_cell(0, 0), ExposeResult::Exposed);
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]);
+        assert!(game.cells_where(|cell| cell.is_flagged).iter().all(|&(x, y)| !game.field[y][x].is_exposed));
+    }
+
+    #[test]
+    fn number_offset_shifts_only_the_shown_numbers() {
+        let mut game = board(&["*..", "...", "..."]);
+        game.toggle_flag(0, 0);
+        game.try_expose_cell(2, 2);
+        assert_eq!(game.to_string(), "F1.\n11.\n...");
+
+        game.config = game.config.clone().with_number_offset(9);
+        assert_eq!(game.to_string(), "Fa.\naa.\n...");
+        assert_eq!(game.cell(1, 0).surrounding_gophers, 1);
+
+        game.config = game.config.clone().with_number_offset(-2);
+        assert_eq!(game.render_chars()[1], vec!['?', '?', '.']);
+
+        let mut lost = board(&["*..", "..2"]);
+        lost.config = lost.config.clone().with_reveal_on_loss(RevealPolicy::Reveal);
+        lost.try_expose_cell(0, 0);
+        assert_eq!(lost.to_string(), "*##\n##*");
+    }
+
+    #[test]
+    fn cascades_never_expose_a_gopher_next_to_a_zero() {
+        let mut game = board(&["*..", "...", "..."]);
+        game.field[1][1].kind = CellKind::AntiGopher;
+        game.recompute_counts();
+        assert_eq!(game.field[1][0].surrounding_gophers, 0);
+
+        assert_eq!(game.try_expose_cell(2, 2), ExposeResult::Win);
+        assert!(!game.cell(0, 0).is_exposed);
+        assert_eq!(game.state(), GameState::Won);
+    }
+
+    #[test]
+    fn anti_gopher_boards_expose_without_revealing_gophers() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Hard).with_anti_gophers(6);
+
+        for seed in 0..20 {
+            let mut game = GopherSweeper::new_seeded(config.clone(), seed);
+
+            for (x, y) in game.cells_where(|cell| !cell.has_gopher() && cell.surrounding_gophers == 0) {
+                game.try_expose_cell(x, y);
+            }
+
+            assert!(game.cells_where(Cell::has_gopher).iter().all(|&(x, y)| !game.field[y][x].is_exposed));
+            assert_eq!(game.state() == GameState::Won, game.remaining_cells == 0);
+        }
+    }
+
+    #[test]
+    fn removing_the_last_wrong_flag_wins() {
+        let mut game = flag_all_gophers(&["2..", "..*"]);
+        game.toggle_flag(1, 1);
+        game.toggle_flag(0, 0);
+        assert_eq!(game.toggle_flag(2, 1), ToggleFlagResult::Flagged);
+
+        assert_eq!(game.toggle_flag(1, 1), ToggleFlagResult::Win);
+        assert_eq!(game.state(), GameState::Won);
+    }
+
+    #[test]
+    fn try_new_rejects_more_gophers_than_cells() {
+        let config = |per_cell| {
+            GameConfig::new(FieldSize::Custom { width: 3, height: 3 }, Difficulty::Exact { gophers: 10 }).with_max_gophers_per_cell(per_cell)
+        };
+
+        assert_eq!(GopherSweeper::try_new(config(1)).err(), Some(ConfigError::TooManyGophers { gophers: 10, capacity: 9 }));
+        assert!(GopherSweeper::try_new(config(2)).is_ok());
+
+        let clamped = GopherSweeper::new(config(1));
+        assert_eq!(gopher_cells(&clamped).len(), 9);
+    }
+
+    #[test]
+    fn try_new_rejects_margins_too_wide_for_the_gophers() {
+        let config = |margin| GameConfig::new(FieldSize::Custom { width: 8, height: 8 }, Difficulty::Exact { gophers: 20 }).with_gopher_margin(margin);
+
+        assert!(GopherSweeper::try_new(config(1)).is_ok());
+        assert_eq!(GopherSweeper::try_new(config(2)).err(), Some(ConfigError::TooManyGophers { gophers: 20, capacity: 16 }));
+        assert_eq!(GopherSweeper::try_new(config(4)).err(), Some(ConfigError::TooManyGophers { gophers: 20, capacity: 0 }));
+        assert!(GopherSweeper::try_new(config(2).with_max_gophers_per_cell(2)).is_ok());
+    }
+
+    #[test]
+    fn expose_row_leaves_protected_cells_alone() {
+        let mut game = board(&["*.*.", "....", "*..."]);
+        game.set_protected(0, 0, true);
+        game.set_protected(3, 0, true);
+
+        assert_eq!(game.expose_row(0, true), ExposeResult::Exposed);
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), vec![(1, 0)]);
+
+        game.set_protected(0, 2, true);
+        assert_eq!(game.expose_column(0, false), ExposeResult::Exposed);
+        assert_eq!(game.state(), GameState::Playing);
+        assert!(game.cell(0, 1).is_exposed && !game.cell(0, 0).is_exposed && !game.cell(0, 2).is_exposed);
+    }
+
+    #[test]
+    fn skipping_a_row_of_only_gophers_reports_nothing_exposed() {
+        let mut game = board(&["*.", "*.", ".."]);
+        game.toggle_flag(1, 0);
+
+        assert_eq!(game.expose_column(0, true), ExposeResult::Exposed);
+        assert_eq!(game.expose_row(0, true), ExposeResult::WasAlreadyExposed);
+        assert_eq!(game.expose_row(1, true), ExposeResult::Exposed);
+        assert_eq!(game.expose_column(0, true), ExposeResult::WasAlreadyExposed);
+        assert_eq!(game.remaining_cells, 2);
+    }
+
+    #[test]
+    fn practice_boards_are_won_by_flagging_every_gopher() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_win_condition(WinCondition::FlagAllGophers);
+        let mut game = GopherSweeper::new_practice(config, 3);
+        let gophers = gopher_cells(&game);
+        let (last, rest) = gophers.split_last().unwrap();
+
+        for &(x, y) in rest {
+            assert_eq!(game.toggle_flag(x, y), ToggleFlagResult::Flagged);
+        }
+
+        assert_eq!(game.state(), GameState::Playing);
+        assert_eq!(game.toggle_flag(last.0, last.1), ToggleFlagResult::Win);
+
+        let mut cleared = GopherSweeper::new_practice(GameConfig::new(FieldSize::Small, Difficulty::Easy), 3);
+        for (x, y) in gophers {
+            cleared.toggle_flag(x, y);
+        }
+        assert_eq!(cleared.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn a_finished_game_ignores_further_moves() {
+        let limit = Duration::from_secs(30);
+        let start = Instant::now();
+
+        let mut game = board(&["*.", ".."]);
+        game.config = game.config.clone().with_time_limit(limit);
+        game.try_expose_cell(1, 1);
+        game.tick(start);
+        game.tick(start + limit);
+
+        assert_eq!(game.try_expose_cell(1, 0), ExposeResult::GameOver);
+        assert_eq!(game.expose_row(1, false), ExposeResult::GameOver);
+        assert_eq!(game.toggle_flag(0, 0), ToggleFlagResult::GameOver);
+        assert_eq!(game.exposed_count(), 1);
+        assert!(!game.field[0][0].is_flagged);
+        assert_eq!(game.state(), GameState::Lost(LossReason::TimeExpired));
+
+        let mut won = board(&["*..", "...", "..."]);
+        won.try_expose_cell(2, 2);
+        assert_eq!(won.try_expose_cell(0, 0), ExposeResult::GameOver);
+        assert_eq!(won.state(), GameState::Won);
+    }
+
+    #[test]
+    fn resume_step_checks_the_cells_it_is_given() {
+        let mut game = board(&["....", "....", "..*."]);
+        let (_, pending) = game.expose_step(0, 0, 2);
+
+        let (result, rest) = game.resume_step([pending.clone(), vec![(9, 9)]].concat(), 1);
+        assert_eq!(result, ExposeResult::Exposed);
+        assert!(!rest.contains(&(9, 9)));
+
+        game.toggle_flag(2, 2);
+        assert_eq!(game.resume_step(vec![(2, 2)], usize::MAX), (ExposeResult::Exposed, vec![]));
+        assert_eq!(game.state(), GameState::Playing);
+
+        game.toggle_flag(2, 2);
+        assert_eq!(game.resume_step([rest, vec![(2, 2)]].concat(), usize::MAX), (ExposeResult::HasGopher, vec![]));
+        assert_eq!(game.state(), GameState::Lost(LossReason::HitGopher));
+        assert!(!game.field[2][2].is_exposed);
+    }
+
+    #[test]
+    fn the_largest_opening_is_exposed_past_its_flagged_cells() {
+        let mut game = board(&["..*....", "..*....", "..*...."]);
+        game.toggle_flag(4, 0);
+
+        assert_eq!(game.expose_largest_opening(), ExposeResult::Exposed);
+        assert!(game.cell(6, 0).is_exposed && game.cell(4, 2).is_exposed);
+        assert!(!game.cell(4, 0).is_exposed && !game.cell(0, 0).is_exposed);
+
+        assert_eq!(game.expose_largest_opening(), ExposeResult::Exposed);
+        assert!(game.cell(0, 0).is_exposed);
+        assert_eq!(game.expose_largest_opening(), ExposeResult::WasAlreadyExposed);
+
+        game.toggle_flag(4, 0);
+        assert_eq!(game.expose_largest_opening(), ExposeResult::Win);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{CellKind, GameConfig, GopherSweeper, MAX_GENERATION_ATTEMPTS};
+use rand::Rng;
+use std::fmt;
+
+/// A rectangular layout of gophers, relative to its top-left corner. Every other
+/// cell inside the rectangle is kept gopher-free when the pattern is placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub gophers: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    InvalidPattern,
+    DoesNotFit,
+    TooManyGophers,
+    GenerationFailed,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::InvalidPattern => write!(f, "pattern gophers lie outside the pattern"),
+            PatternError::DoesNotFit => write!(f, "pattern is larger than the board"),
+            PatternError::TooManyGophers => write!(f, "the gopher count cannot accommodate the pattern"),
+            PatternError::GenerationFailed => write!(f, "no layout around the pattern keeps every number under the cap"),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl GopherSweeper {
+    /// Places `pattern` at a random position clear of the gopher margin, then
+    /// scatters the rest of the configured gophers and any anti-gophers outside
+    /// of it. The layout is generated immediately, so any safe radius is ignored.
+    pub fn new_with_pattern<R: Rng>(config: GameConfig, pattern: &Pattern, rng: &mut R) -> Result<Self, PatternError> {
+        let (width, height) = config.size();
+        let gophers = config.gophers();
+        let max_per_cell = config.max_gophers_per_cell() as usize;
+        let max_number = config.max_cell_number().unwrap_or(u8::MAX);
+
+        let margin = config.gopher_margin();
+        let (inner_width, inner_height) = (width.saturating_sub(2 * margin), height.saturating_sub(2 * margin));
+
+        if pattern.width == 0
+            || pattern.height == 0
+            || pattern.gophers.iter().any(|&(x, y)| x >= pattern.width || y >= pattern.height)
+        {
+            return Err(PatternError::InvalidPattern);
+        }
+
+        if pattern.width > inner_width || pattern.height > inner_height {
+            return Err(PatternError::DoesNotFit);
+        }
+
+        let mut pattern_gophers = pattern.gophers.clone();
+        pattern_gophers.sort_unstable();
+        pattern_gophers.dedup();
+
+        let remaining_gophers = gophers
+            .checked_sub(pattern_gophers.len())
+            .filter(|&remaining| remaining <= (inner_width * inner_height - pattern.width * pattern.height) * max_per_cell)
+            .ok_or(PatternError::TooManyGophers)?;
+
+        let in_margin = |x: usize, y: usize| x < margin || y < margin || x + margin >= width || y + margin >= height;
+        let mut result = GopherSweeper::empty(config, None);
+
+        for _ in 0..MAX_GENERATION_ATTEMPTS {
+            for cell in result.field.iter_mut().flatten() {
+                cell.gopher_count = 0;
+                cell.kind = CellKind::Normal;
+            }
+
+            let offset_x = margin + rng.gen_range(0..=inner_width - pattern.width);
+            let offset_y = margin + rng.gen_range(0..=inner_height - pattern.height);
+
+            for &(x, y) in &pattern_gophers {
+                result.field[offset_y + y][offset_x + x].gopher_count = 1;
+            }
+
+            let placed = result.scatter_gophers(rng, remaining_gophers, |x, y| {
+                in_margin(x, y)
+                    || (offset_x..offset_x + pattern.width).contains(&x) && (offset_y..offset_y + pattern.height).contains(&y)
+            });
+
+            result.scatter_anti_gophers(rng);
+            result.recompute_counts();
+
+            if placed && result.field.iter().flatten().all(|cell| cell.has_gopher() || cell.surrounding_gophers <= max_number) {
+                result.generated = true;
+                return Ok(result);
+            }
+        }
+
+        Err(PatternError::GenerationFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::gopher_cells;
+    use crate::{Difficulty, FieldSize};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn contains_pattern(game: &GopherSweeper, pattern: &Pattern) -> bool {
+        let (width, height) = game.config.size();
+
+        (0..=height - pattern.height).any(|oy| {
+            (0..=width - pattern.width).any(|ox| {
+                (0..pattern.height).all(|y| {
+                    (0..pattern.width).all(|x| game.field[oy + y][ox + x].has_gopher() == pattern.gophers.contains(&(x, y)))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn generated_boards_contain_the_pattern() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 5, height: 5 }, Difficulty::Exact { gophers: 6 }).with_safe_radius(1);
+        let pattern = Pattern { width: 5, height: 2, gophers: vec![(1, 0), (3, 0), (1, 0)] };
+
+        for seed in 0..20 {
+            let mut game = GopherSweeper::new_with_pattern(config(), &pattern, &mut StdRng::seed_from_u64(seed)).unwrap();
+            let layout = gopher_cells(&game);
+
+            assert!(contains_pattern(&game, &pattern));
+            assert_eq!(layout.len(), 6);
+
+            let (x, y) = (0..5).flat_map(|y| (0..5).map(move |x| (x, y))).find(|&(x, y)| !game.field[y][x].has_gopher()).unwrap();
+            game.try_expose_cell(x, y);
+            assert_eq!(gopher_cells(&game), layout);
+        }
+    }
+
+    #[test]
+    fn patterns_that_cannot_be_placed_are_rejected() {
+        let config = |gophers| GameConfig::new(FieldSize::Custom { width: 4, height: 4 }, Difficulty::Exact { gophers });
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut place = |gophers, pattern: Pattern| GopherSweeper::new_with_pattern(config(gophers), &pattern, &mut rng).err();
+
+        assert_eq!(place(2, Pattern { width: 2, height: 2, gophers: vec![(2, 0)] }), Some(PatternError::InvalidPattern));
+        assert_eq!(place(2, Pattern { width: 0, height: 2, gophers: vec![] }), Some(PatternError::InvalidPattern));
+        assert_eq!(place(2, Pattern { width: 5, height: 1, gophers: vec![] }), Some(PatternError::DoesNotFit));
+        assert_eq!(place(1, Pattern { width: 2, height: 1, gophers: vec![(0, 0), (1, 0)] }), Some(PatternError::TooManyGophers));
+        assert_eq!(place(13, Pattern { width: 2, height: 2, gophers: vec![] }), Some(PatternError::TooManyGophers));
+        assert_eq!(place(12, Pattern { width: 2, height: 2, gophers: vec![] }), None);
+        assert_eq!(place(1, Pattern { width: 4, height: 4, gophers: vec![(3, 3), (3, 3)] }), None);
+    }
+
+    #[test]
+    fn pattern_boards_keep_out_of_the_margin_and_scatter_anti_gophers() {
+        let config = GameConfig::new(FieldSize::Custom { width: 7, height: 7 }, Difficulty::Exact { gophers: 8 })
+            .with_gopher_margin(1)
+            .with_anti_gophers(3);
+        let pattern = Pattern { width: 2, height: 1, gophers: vec![(0, 0)] };
+
+        for seed in 0..20 {
+            let game = GopherSweeper::new_with_pattern(config.clone(), &pattern, &mut StdRng::seed_from_u64(seed)).unwrap();
+            let gophers = gopher_cells(&game);
+
+            assert!(contains_pattern(&game, &pattern));
+            assert_eq!(gophers.len(), 8);
+            assert!(gophers.iter().all(|&(x, y)| (1..6).contains(&x) && (1..6).contains(&y)));
+            assert_eq!(game.field.iter().flatten().filter(|cell| cell.kind == CellKind::AntiGopher).count(), 3);
+        }
+    }
+
+    #[test]
+    fn pattern_boards_keep_every_number_under_the_cap() {
+        let config = || GameConfig::new(FieldSize::Custom { width: 6, height: 6 }, Difficulty::Exact { gophers: 8 }).with_max_cell_number(2);
+        let pattern = Pattern { width: 1, height: 1, gophers: vec![(0, 0)] };
+
+        for seed in 0..20 {
+            let game = GopherSweeper::new_with_pattern(config(), &pattern, &mut StdRng::seed_from_u64(seed)).unwrap();
+            assert!(game.field.iter().flatten().all(|cell| cell.has_gopher() || cell.surrounding_gophers <= 2));
+        }
+
+        let ring = Pattern { width: 3, height: 3, gophers: vec![(0, 0), (1, 0), (2, 0), (0, 1), (2, 1), (0, 2), (1, 2), (2, 2)] };
+        let result = GopherSweeper::new_with_pattern(config(), &ring, &mut StdRng::seed_from_u64(0));
+        assert_eq!(result.err(), Some(PatternError::GenerationFailed));
+    }
+
+    #[test]
+    fn the_margin_shrinks_the_room_for_a_pattern() {
+        let config = |gophers| GameConfig::new(FieldSize::Custom { width: 6, height: 6 }, Difficulty::Exact { gophers }).with_gopher_margin(1);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut place = |gophers, pattern: Pattern| GopherSweeper::new_with_pattern(config(gophers), &pattern, &mut rng).err();
+
+        assert_eq!(place(1, Pattern { width: 5, height: 1, gophers: vec![] }), Some(PatternError::DoesNotFit));
+        assert_eq!(place(13, Pattern { width: 2, height: 2, gophers: vec![] }), Some(PatternError::TooManyGophers));
+        assert_eq!(place(12, Pattern { width: 2, height: 2, gophers: vec![] }), None);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{CellEventKind, CellKind, GopherSweeper};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+const MAX_RETRY_STEPS: usize = 100_000;
+
+impl GopherSweeper {
+    /// Turns a lost game back into a running one on a fresh layout that agrees
+    /// with every exposed number: exposed gophers are covered again, each one
+    /// reported to the observer as `Unexposed`, and the same number of gophers is
+    /// dealt anew among the unexposed cells. Keeps the old layout when the search
+    /// for a new one runs out of steps. Does nothing unless the game was lost.
+    pub fn retry_from_here(&mut self, rng: &mut impl Rng) {
+        if self.loss.is_none() { return }
+
+        for (x, y) in self.cells_where(|cell| cell.is_exposed && cell.has_gopher()) {
+            self.field[y][x].is_exposed = false;
+            self.notify(x, y, CellEventKind::Unexposed);
+        }
+
+        let max_per_cell = self.config.max_gophers_per_cell();
+        let gophers: usize = self.field.iter().flatten().map(|cell| cell.gopher_count as usize).sum();
+
+        let open = self.cells_where(|cell| !cell.is_exposed && cell.kind == CellKind::Normal);
+        let borders_exposed = |&(x, y): &(usize, usize)| {
+            self.surrounding_cells_coords(x, y).into_iter().any(|(x, y)| self.field[y][x].is_exposed)
+        };
+
+        let mut frontier: Vec<(usize, usize)> = open.iter().copied().filter(borders_exposed).collect();
+        let interior: Vec<(usize, usize)> = open.into_iter().filter(|cell| !borders_exposed(cell)).collect();
+        frontier.shuffle(rng);
+
+        let index: HashMap<(usize, usize), usize> = frontier.iter().enumerate().map(|(i, &cell)| (cell, i)).collect();
+        let mut search = Placement {
+            values: vec![0; frontier.len()],
+            requirements: Vec::new(),
+            cell_requirements: vec![Vec::new(); frontier.len()],
+            max_per_cell,
+            gophers,
+            interior_capacity: interior.len() * max_per_cell as usize,
+            steps: 0,
+        };
+
+        for (x, y) in self.cells_where(|cell| cell.is_exposed) {
+            let neighbors: Vec<usize> = self.surrounding_cells_coords(x, y)
+                .into_iter()
+                .filter_map(|cell| index.get(&cell).copied())
+                .collect();
+
+            if neighbors.is_empty() {
+                continue;
+            }
+
+            let required = neighbors.iter().map(|&i| self.field[frontier[i].1][frontier[i].0].gopher_count as usize).sum();
+
+            for &i in &neighbors {
+                search.cell_requirements[i].push(search.requirements.len());
+            }
+
+            search.requirements.push(Requirement { placed: 0, unassigned: neighbors.len(), required });
+        }
+
+        if search.run(rng, 0, 0) {
+            for &(x, y) in frontier.iter().chain(&interior) {
+                self.field[y][x].gopher_count = 0;
+                self.field[y][x].variant = 0;
+            }
+
+            let mut remaining = gophers;
+
+            for (&(x, y), &value) in frontier.iter().zip(&search.values) {
+                self.field[y][x].gopher_count = value;
+                remaining -= value as usize;
+            }
+
+            while remaining > 0 {
+                let (x, y) = interior[rng.gen_range(0..interior.len())];
+                let cell = &mut self.field[y][x];
+
+                if cell.gopher_count < max_per_cell {
+                    cell.gopher_count += 1;
+                    remaining -= 1;
+                }
+            }
+
+            self.assign_variants(rng);
+        }
+
+        self.recompute_counts();
+
+        self.loss = None;
+        self.won = false;
+        self.started_at = None;
+    }
+}
+
+struct Requirement {
+    placed: usize,
+    unassigned: usize,
+    required: usize,
+}
+
+/// A randomized depth-first search for per-cell gopher counts on the frontier
+/// that meet every requirement and leave a count the interior can hold.
+struct Placement {
+    values: Vec<u8>,
+    requirements: Vec<Requirement>,
+    cell_requirements: Vec<Vec<usize>>,
+    max_per_cell: u8,
+    gophers: usize,
+    interior_capacity: usize,
+    steps: usize,
+}
+
+impl Placement {
+    fn run(&mut self, rng: &mut impl Rng, cell: usize, placed: usize) -> bool {
+        if cell == self.values.len() {
+            return self.gophers - placed <= self.interior_capacity;
+        }
+
+        self.steps += 1;
+        if self.steps > MAX_RETRY_STEPS { return false }
+
+        let mut choices: Vec<u8> = (0..=self.max_per_cell).collect();
+        choices.shuffle(rng);
+
+        for value in choices {
+            let count = value as usize;
+
+            if placed + count > self.gophers {
+                continue;
+            }
+
+            let consistent = self.cell_requirements[cell].iter().all(|&i| {
+                let requirement = &self.requirements[i];
+                let placed = requirement.placed + count;
+                let capacity = (requirement.unassigned - 1) * self.max_per_cell as usize;
+
+                placed <= requirement.required && placed + capacity >= requirement.required
+            });
+
+            if !consistent {
+                continue;
+            }
+
+            for &i in &self.cell_requirements[cell] {
+                self.requirements[i].placed += count;
+                self.requirements[i].unassigned -= 1;
+            }
+
+            self.values[cell] = value;
+
+            if self.run(rng, cell + 1, placed + count) {
+                return true;
+            }
+
+            for &i in &self.cell_requirements[cell] {
+                self.requirements[i].placed -= count;
+                self.requirements[i].unassigned += 1;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cell, CellEvent, Difficulty, FieldSize, GameConfig, GameState, LossReason, RevealPolicy};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    fn lost_game(config: GameConfig, seed: u64) -> GopherSweeper {
+        let mut game = GopherSweeper::new_seeded(config, seed);
+
+        let (x, y) = game.cells_where(|cell| !cell.has_gopher() && cell.surrounding_gophers == 0)[0];
+        game.try_expose_cell(x, y);
+        let (x, y) = game.cells_where(Cell::has_gopher)[0];
+        game.try_expose_cell(x, y);
+
+        game
+    }
+
+    fn assert_retry_keeps_the_numbers(mut game: GopherSweeper, seed: u64) {
+        assert_eq!(game.state(), GameState::Lost(LossReason::HitGopher));
+        let hit = game.cells_where(|cell| cell.is_exposed && cell.has_gopher());
+        let exposed = game.cells_where(|cell| cell.is_exposed && !cell.has_gopher());
+        let numbers: Vec<u8> = exposed.iter().map(|&(x, y)| game.field[y][x].surrounding_gophers).collect();
+        let gophers: usize = game.field.iter().flatten().map(|cell| cell.gopher_count as usize).sum();
+
+        game.retry_from_here(&mut ChaCha8Rng::seed_from_u64(seed));
+
+        assert_eq!(game.state(), GameState::Playing);
+        assert!(hit.iter().all(|&(x, y)| !game.field[y][x].is_exposed));
+        assert_eq!(game.cells_where(|cell| cell.is_exposed), exposed);
+        assert_eq!(exposed.iter().map(|&(x, y)| game.field[y][x].surrounding_gophers).collect::<Vec<_>>(), numbers);
+        assert_eq!(game.field.iter().flatten().map(|cell| cell.gopher_count as usize).sum::<usize>(), gophers);
+        assert!(game.field.iter().flatten().all(|cell| cell.gopher_count <= game.config.max_gophers_per_cell()));
+    }
+
+    #[test]
+    fn retry_keeps_exposed_numbers_consistent() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Hard).with_max_gophers_per_cell(2).with_anti_gophers(4);
+
+        for seed in 0..20 {
+            assert_retry_keeps_the_numbers(lost_game(config.clone(), seed), seed);
+        }
+    }
+
+    #[test]
+    fn retry_restarts_the_clock_after_a_timeout() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_time_limit(Duration::from_secs(10));
+        let mut game = GopherSweeper::new_seeded(config, 1);
+        let start = Instant::now();
+
+        let (x, y) = game.cells_where(|cell| !cell.has_gopher() && cell.surrounding_gophers == 0)[0];
+        game.try_expose_cell(x, y);
+        game.tick(start);
+        assert_eq!(game.tick(start + Duration::from_secs(10)), GameState::Lost(LossReason::TimeExpired));
+
+        game.retry_from_here(&mut ChaCha8Rng::seed_from_u64(1));
+        assert_eq!(game.tick(start + Duration::from_secs(15)), GameState::Playing);
+        assert_eq!(game.tick(start + Duration::from_secs(24)), GameState::Playing);
+        assert_eq!(game.tick(start + Duration::from_secs(25)), GameState::Lost(LossReason::TimeExpired));
+    }
+
+    #[test]
+    fn retry_does_nothing_unless_the_game_was_lost() {
+        let mut game = GopherSweeper::new_seeded(GameConfig::new(FieldSize::Small, Difficulty::Easy), 0);
+        let before = game.layout_hash();
+
+        game.retry_from_here(&mut ChaCha8Rng::seed_from_u64(0));
+        assert_eq!(game.layout_hash(), before);
+
+        for (x, y) in game.cells_where(|cell| !cell.has_gopher()) {
+            game.try_expose_cell(x, y);
+        }
+        assert_eq!(game.state(), GameState::Won);
+
+        game.retry_from_here(&mut ChaCha8Rng::seed_from_u64(0));
+        assert_eq!(game.state(), GameState::Won);
+        assert_eq!(game.layout_hash(), before);
+    }
+
+    #[test]
+    fn retry_notifies_covered_gophers() {
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_reveal_on_loss(RevealPolicy::Reveal);
+    let mut game = lost_game(config, 3);
+        let hit = game.cells_where(|cell| cell.is_exposed && cell.has_gopher());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        game.set_observer(Box::new(move |event: CellEvent| sink.lock().unwrap().push((event.x, event.y, event.kind))));
+        game.retry_from_here(&mut ChaCha8Rng::seed_from_u64(3));
+
+        assert!(!hit.is_empty());
+        assert_eq!(*events.lock().unwrap(), hit.iter().map(|&(x, y)| (x, y, CellEventKind::Unexposed)).collect::<Vec<_>>());
+    }
+}
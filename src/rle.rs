@@ -0,0 +1,189 @@
+use crate::{Difficulty, FieldSize, GameConfig, GopherSweeper, DEFAULT_MAX_CELLS};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RleError {
+    MissingHeader,
+    InvalidDimensions,
+    InvalidRun(usize),
+    TooManyCells,
+    BoardTooLarge,
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::MissingHeader => write!(f, "missing `<width>x<height>:` header"),
+            RleError::InvalidDimensions => write!(f, "invalid board dimensions"),
+            RleError::InvalidRun(position) => write!(f, "invalid run at byte {position}"),
+            RleError::TooManyCells => write!(f, "runs describe more cells than the board has"),
+            RleError::BoardTooLarge => write!(f, "board dimensions exceed the default cell limit"),
+        }
+    }
+}
+
+impl std::error::Error for RleError {}
+
+impl GopherSweeper {
+    /// Encodes the layout as `<width>x<height>:` followed by row-major runs of
+    /// `[count]<cell>`, where `<cell>` is `.` (no gopher), `*` (one gopher) or `[n]`
+    /// (a heavy cell with `n` gophers) and a missing count means 1. Trailing empty
+    /// cells are omitted, e.g. `4x3:5.*` is a 4x3 board with a gopher at (1, 1).
+    pub fn to_rle(&self) -> String {
+        let (width, height) = self.config.size();
+        let mut result = format!("{width}x{height}:");
+
+        let counts: Vec<u8> = self.field.iter().flatten().map(|cell| cell.gopher_count).collect();
+        let trimmed_len = counts.iter().rposition(|&count| count > 0).map_or(0, |i| i + 1);
+
+        for run in counts[..trimmed_len].chunk_by(|a, b| a == b) {
+            if run.len() > 1 {
+                result.push_str(&run.len().to_string());
+            }
+
+            match run[0] {
+                0 => result.push('.'),
+                1 => result.push('*'),
+                count => result.push_str(&format!("[{count}]")),
+            }
+        }
+
+        result
+    }
+
+    pub fn from_rle(s: &str) -> Result<GopherSweeper, RleError> {
+        let (header, runs) = s.trim().split_once(':').ok_or(RleError::MissingHeader)?;
+        let (width, height) = header.split_once('x').ok_or(RleError::MissingHeader)?;
+
+        let width: usize = width.trim().parse().map_err(|_| RleError::InvalidDimensions)?;
+        let height: usize = height.trim().parse().map_err(|_| RleError::InvalidDimensions)?;
+
+        if width == 0 || height == 0 {
+            return Err(RleError::InvalidDimensions);
+        }
+
+        let cells = width.checked_mul(height)
+            .filter(|&cells| cells <= DEFAULT_MAX_CELLS)
+            .ok_or(RleError::BoardTooLarge)?;
+
+        let mut counts = Vec::with_capacity(cells);
+        let mut chars = runs.char_indices().filter(|(_, c)| !c.is_whitespace()).peekable();
+
+        while let Some(&(start, _)) = chars.peek() {
+            let mut run = String::new();
+
+            while let Some(&(_, c)) = chars.peek().filter(|(_, c)| c.is_ascii_digit()) {
+                run.push(c);
+                chars.next();
+            }
+
+            let run: usize = if run.is_empty() { 1 } else { run.parse().map_err(|_| RleError::InvalidRun(start))? };
+
+            let count = match chars.next() {
+                Some((_, '.')) => 0,
+                Some((_, '*')) => 1,
+                Some((_, '[')) => {
+                    let mut count = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) => break,
+                            Some((_, c)) if c.is_ascii_digit() => count.push(c),
+                            _ => return Err(RleError::InvalidRun(start)),
+                        }
+                    }
+
+                    count.parse().map_err(|_| RleError::InvalidRun(start))?
+                }
+                _ => return Err(RleError::InvalidRun(start)),
+            };
+
+            if run > cells - counts.len() {
+                return Err(RleError::TooManyCells);
+            }
+
+            counts.extend(std::iter::repeat_n(count, run));
+        }
+
+        counts.resize(cells, 0);
+
+        let gophers = counts.iter().map(|&count| count as usize).sum();
+        let max_per_cell = counts.iter().copied().max().unwrap_or(0);
+
+        let config = GameConfig::new(FieldSize::Custom { width, height }, Difficulty::Exact { gophers })
+            .with_max_gophers_per_cell(max_per_cell);
+
+        let mut result = GopherSweeper::empty(config, None);
+
+        for (cell, count) in result.field.iter_mut().flatten().zip(counts) {
+            cell.gopher_count = count;
+        }
+
+        result.recompute_counts();
+        result.generated = true;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_large_board_round_trips() {
+        let config = GameConfig::new(FieldSize::Custom { width: 300, height: 200 }, Difficulty::Exact { gophers: 40 });
+        let game = GopherSweeper::new_seeded(config, 7);
+
+        let rle = game.to_rle();
+        let decoded = GopherSweeper::from_rle(&rle).unwrap();
+
+        assert!(rle.len() < 400);
+        assert!(decoded.field.iter().flatten().map(|cell| cell.gopher_count).eq(game.field.iter().flatten().map(|cell| cell.gopher_count)));
+        assert_eq!(decoded.config.gophers(), 40);
+        assert_eq!(decoded.to_rle(), rle);
+    }
+
+    #[test]
+    fn heavy_cells_and_whitespace_decode() {
+        let game = GopherSweeper::from_rle(" 3x2: 2. [3] *\n").unwrap();
+
+        assert_eq!(game.field[0][2].gopher_count, 3);
+        assert_eq!(game.field[1][0].gopher_count, 1);
+        assert_eq!(game.field[1][1].surrounding_gophers, 4);
+        assert_eq!(game.config.gophers(), 4);
+        assert_eq!(game.config.max_gophers_per_cell(), 3);
+        assert_eq!(game.to_rle(), "3x2:2.[3]*");
+
+        assert_eq!(GopherSweeper::from_rle("2x2:").unwrap().to_rle(), "2x2:");
+    }
+
+    #[test]
+    fn malformed_layouts_are_rejected() {
+        let error = |s| GopherSweeper::from_rle(s).err();
+
+        assert_eq!(error("3x2"), Some(RleError::MissingHeader));
+        assert_eq!(error("3y2:."), Some(RleError::MissingHeader));
+        assert_eq!(error("0x2:"), Some(RleError::InvalidDimensions));
+        assert_eq!(error("ax2:"), Some(RleError::InvalidDimensions));
+        assert_eq!(error("3x2:*2"), Some(RleError::InvalidRun(1)));
+        assert_eq!(error("3x2:.[x]"), Some(RleError::InvalidRun(1)));
+        assert_eq!(error("3x2:5.2*"), Some(RleError::TooManyCells));
+    }
+
+    #[test]
+    fn doc_example_decodes() {
+        let game = GopherSweeper::from_rle("4x3:5.*").unwrap();
+
+        assert_eq!(game.cells_where(|cell| cell.has_gopher()), vec![(1, 1)]);
+        assert_eq!(game.to_rle(), "4x3:5.*");
+    }
+
+    #[test]
+    fn oversized_headers_are_rejected() {
+        assert_eq!(GopherSweeper::from_rle("100000x100000:*").err(), Some(RleError::BoardTooLarge));
+        assert_eq!(GopherSweeper::from_rle("18446744073709551615x2:").err(), Some(RleError::BoardTooLarge));
+        assert_eq!(GopherSweeper::from_rle("3x1:18446744073709551615.").err(), Some(RleError::TooManyCells));
+        assert_eq!(GopherSweeper::from_rle("3x1:2.2*").err(), Some(RleError::TooManyCells));
+    }
+}
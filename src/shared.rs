@@ -0,0 +1,60 @@
+use crate::{BoardSnapshot, ExposeResult, GopherSweeper, ToggleFlagResult};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+#[derive(Clone)]
+pub struct SharedGame(Arc<Mutex<GopherSweeper>>);
+
+impl SharedGame {
+    pub fn new(game: GopherSweeper) -> Self {
+        SharedGame(Arc::new(Mutex::new(game)))
+    }
+
+    pub fn expose(&self, x: usize, y: usize) -> ExposeResult {
+        self.lock().try_expose_cell(x, y)
+    }
+
+    pub fn flag(&self, x: usize, y: usize) -> ToggleFlagResult {
+        self.lock().toggle_flag(x, y)
+    }
+
+    pub fn snapshot(&self) -> BoardSnapshot {
+        self.lock().snapshot()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, GopherSweeper> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::board;
+    use crate::VisibleCell;
+    use std::thread;
+
+    #[test]
+    fn threads_expose_and_flag_different_cells_of_one_game() {
+        let shared = SharedGame::new(board(&["********", "........"]));
+
+        let handles: Vec<_> = (0..8)
+            .map(|x| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    shared.flag(x, 0);
+                    shared.expose(x, 1)
+                })
+            })
+            .collect();
+        let results: Vec<ExposeResult> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+
+        assert_eq!(results.iter().filter(|&&result| result == ExposeResult::Win).count(), 1);
+        assert_eq!(results.iter().filter(|&&result| result == ExposeResult::Exposed).count(), 7);
+
+        let snapshot = shared.snapshot();
+        assert_eq!(snapshot.remaining_cells, 0);
+        assert!(snapshot.cells[0].iter().all(|&cell| cell == VisibleCell::Flagged));
+        assert_eq!(snapshot.cell(0, 1), VisibleCell::Exposed(2));
+        assert!(snapshot.cells[1][1..7].iter().all(|&cell| cell == VisibleCell::Exposed(3)));
+    }
+}
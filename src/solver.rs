@@ -0,0 +1,1166 @@
+use crate::{ExposeResult, GopherSweeper};
+use std::collections::{HashMap, HashSet};
+
+const MAX_ESTIMATED_CONFIGURATIONS: usize = 100_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CertainMoves {
+    pub safe: Vec<(usize, usize)>,
+    pub gophers: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frontier {
+    pub unexposed: HashSet<(usize, usize)>,
+    pub exposed: HashSet<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveKind {
+    Safe,
+    Gopher,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedMove {
+    pub cell: (usize, usize),
+    pub kind: MoveKind,
+    pub rationale: String,
+}
+
+/// An exposed number seen as a constraint: exactly `gophers` of `cells` (its
+/// unexposed, unflagged neighbors, in row-major order) hold a gopher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub origin: (usize, usize),
+    pub cells: Vec<(usize, usize)>,
+    pub gophers: usize,
+}
+
+impl GopherSweeper {
+    /// Deduces the unexposed, unflagged cells that are certainly safe or certainly
+    /// gophers from exposed numbers and flags alone, treating flags as gophers.
+    /// Deductions assume one gopher per cell.
+    pub fn certain_moves(&self) -> CertainMoves {
+        let mut constraints = self.constraints();
+        let mut safe = HashSet::new();
+        let mut gophers = HashSet::new();
+
+        loop {
+            let mut changed = false;
+
+            for constraint in &mut constraints {
+                let known_gophers = constraint.cells.iter().filter(|cell| gophers.contains(*cell)).count();
+
+                constraint.cells.retain(|cell| !safe.contains(cell) && !gophers.contains(cell));
+                constraint.gophers = constraint.gophers.saturating_sub(known_gophers);
+            }
+
+            constraints.retain(|constraint| !constraint.cells.is_empty());
+
+            for constraint in &constraints {
+                changed |= mark_if_determined(&constraint.cells, constraint.gophers, &mut safe, &mut gophers);
+            }
+
+            if !changed {
+                let mut by_cell: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+
+                for (i, constraint) in constraints.iter().enumerate() {
+                    for &cell in &constraint.cells {
+                        by_cell.entry(cell).or_default().push(i);
+                    }
+                }
+
+                for (i, smaller) in constraints.iter().enumerate() {
+                    let candidates: HashSet<usize> = by_cell[&smaller.cells[0]].iter().copied().collect();
+
+                    for j in candidates {
+                        let larger = &constraints[j];
+
+                        if i == j
+                            || larger.cells.len() <= smaller.cells.len()
+                            || larger.gophers < smaller.gophers
+                            || !smaller.cells.iter().all(|cell| larger.cells.contains(cell))
+                        {
+                            continue;
+                        }
+
+                        let difference: Vec<(usize, usize)> = larger.cells.iter()
+                            .copied()
+                            .filter(|cell| !smaller.cells.contains(cell))
+                            .collect();
+
+                        changed |= mark_if_determined(
+                            &difference,
+                            larger.gophers - smaller.gophers,
+                            &mut safe,
+                            &mut gophers,
+                        );
+                    }
+                }
+            }
+
+            if !changed { break }
+        }
+
+        CertainMoves {
+            safe: sorted(safe),
+            gophers: sorted(gophers),
+        }
+    }
+
+    /// The unexposed cells touching an exposed cell, and the exposed cells
+    /// touching an unexposed one.
+    pub fn frontier(&self) -> Frontier {
+        let (width, height) = self.config.size();
+        let mut result = Frontier::default();
+
+        for y in 0..height {
+            for x in 0..width {
+                let is_exposed = self.field[y][x].is_exposed;
+
+                let borders_other = self.surrounding_cells_coords(x, y)
+                    .into_iter()
+                    .any(|(x, y)| self.field[y][x].is_exposed != is_exposed);
+
+                if !borders_other {
+                    continue;
+                }
+
+                if is_exposed {
+                    result.exposed.insert((x, y));
+                } else {
+                    result.unexposed.insert((x, y));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Finds one certain move together with a human-readable explanation of the
+    /// rule that forces it, preferring moves that follow from a single number.
+    pub fn next_explained_move(&self) -> Option<ExplainedMove> {
+        let constraints = self.constraints();
+
+        for constraint in &constraints {
+            let (x, y) = constraint.origin;
+            let number = self.field[y][x].surrounding_gophers;
+            let flagged = number as usize - constraint.gophers;
+            let (cx, cy) = constraint.cells[0];
+
+            if constraint.gophers == 0 {
+                return Some(ExplainedMove {
+                    cell: (cx, cy),
+                    kind: MoveKind::Safe,
+                    rationale: format!(
+                        "cell ({x}, {y}) shows {number} and already has {flagged} flagged {}, so ({cx}, {cy}) is safe",
+                        plural(flagged, "neighbor"),
+                    ),
+                });
+            }
+
+            if constraint.gophers == constraint.cells.len() {
+                let hidden = constraint.cells.len();
+
+                return Some(ExplainedMove {
+                    cell: (cx, cy),
+                    kind: MoveKind::Gopher,
+                    rationale: if flagged == 0 {
+                        format!(
+                            "cell ({x}, {y}) shows {number} with {hidden} unexposed {}, so ({cx}, {cy}) is a gopher",
+                            plural(hidden, "neighbor"),
+                        )
+                    } else {
+                        format!(
+                            "cell ({x}, {y}) shows {number} with {flagged} {} and {hidden} other unexposed {}, so ({cx}, {cy}) is a gopher",
+                            plural(flagged, "flag"),
+                            plural(hidden, "neighbor"),
+                        )
+                    },
+                });
+            }
+        }
+
+        for smaller in &constraints {
+            for larger in &constraints {
+                if larger.cells.len() <= smaller.cells.len()
+                    || larger.gophers < smaller.gophers
+                    || !smaller.cells.iter().all(|cell| larger.cells.contains(cell))
+                {
+                    continue;
+                }
+
+                let difference: Vec<(usize, usize)> = larger.cells.iter()
+                    .copied()
+                    .filter(|cell| !smaller.cells.contains(cell))
+                    .collect();
+                let gophers = larger.gophers - smaller.gophers;
+
+                let kind = if gophers == 0 {
+                    MoveKind::Safe
+                } else if gophers == difference.len() {
+                    MoveKind::Gopher
+                } else {
+                    continue;
+                };
+
+                let (sx, sy) = smaller.origin;
+                let (lx, ly) = larger.origin;
+                let (cx, cy) = difference[0];
+
+                return Some(ExplainedMove {
+                    cell: (cx, cy),
+                    rationale: format!(
+                        "cell ({sx}, {sy}) needs {} {} among neighbors it shares with cell ({lx}, {ly}), \
+                         which needs {} in total, so ({cx}, {cy}) is {}",
+                        smaller.gophers,
+                        plural(smaller.gophers, "gopher"),
+                        larger.gophers,
+                        if kind == MoveKind::Safe { "safe" } else { "a gopher" },
+                    ),
+                    kind,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// True when safe cells remain but no certain safe move can be deduced,
+    /// so the player has to guess.
+    pub fn is_stuck(&self) -> bool {
+        self.remaining_cells > 0 && self.certain_moves().safe.is_empty()
+    }
+
+    /// The same check as `is_stuck`, named for offering a restart: the only way
+    /// forward is a guess.
+    pub fn is_forced_guess(&self) -> bool {
+        self.is_stuck()
+    }
+
+    /// How many cells [`certain_moves`](Self::certain_moves) can currently decide,
+    /// safe and gopher alike.
+    pub fn forced_move_count(&self) -> usize {
+        let moves = self.certain_moves();
+        moves.safe.len() + moves.gophers.len()
+    }
+
+    /// Exposed numbers that have more flagged neighbors than their number, or too
+    /// few flagged and unexposed neighbors left to ever be satisfied, even with
+    /// each of them holding the most gophers a cell can.
+    pub fn unsatisfied_numbers(&self) -> Vec<(usize, usize)> {
+        let max_per_cell = self.config.max_gophers_per_cell() as usize;
+
+        self.cells_where(|cell| cell.is_exposed && !cell.has_gopher())
+            .into_iter()
+            .filter(|&(x, y)| {
+                let number = self.field[y][x].surrounding_gophers as usize;
+                let hidden: Vec<bool> = self.surrounding_cells_coords(x, y)
+                    .into_iter()
+                    .map(|(x, y)| &self.field[y][x])
+                    .filter(|cell| !cell.is_exposed)
+                    .map(|cell| cell.is_flagged)
+                    .collect();
+                let flagged = hidden.iter().filter(|&&is_flagged| is_flagged).count();
+
+                flagged > number || hidden.len() * max_per_cell < number
+            })
+            .collect()
+    }
+
+    /// Groups of exposed numbers that can't all be satisfied, which means a flag is
+    /// misplaced: each of `unsatisfied_numbers` on its own, then every pair of
+    /// constraints whose shared cells can't hold a count that works for both.
+    /// Each group is in row-major order.
+    pub fn contradictions(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut result: Vec<Vec<(usize, usize)>> = self.unsatisfied_numbers().into_iter().map(|cell| vec![cell]).collect();
+        let constraints = self.constraints();
+
+        for (i, a) in constraints.iter().enumerate() {
+            for b in &constraints[i + 1..] {
+                let shared = a.cells.iter().filter(|cell| b.cells.contains(cell)).count();
+
+                if shared == 0 {
+                    continue;
+                }
+
+                let fits = |constraint: &Constraint, in_shared: usize| {
+                    in_shared <= constraint.gophers && constraint.gophers - in_shared <= constraint.cells.len() - shared
+                };
+
+                if !(0..=shared).any(|in_shared| fits(a, in_shared) && fits(b, in_shared)) {
+                    result.push(vec![a.origin, b.origin]);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Enumerates every gopher placement on the frontier (the unexposed, unflagged
+    /// cells next to exposed numbers) consistent with the visible numbers and flags.
+    /// Gives up with `None` as soon as more than `max` placements are found.
+    pub fn consistent_configurations(&self, max: usize) -> Option<Vec<HashSet<(usize, usize)>>> {
+        let constraints = self.constraints();
+
+        let mut cells: Vec<(usize, usize)> = constraints.iter()
+            .flat_map(|constraint| constraint.cells.iter().copied())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+
+        let index: HashMap<(usize, usize), usize> = cells.iter()
+            .enumerate()
+            .map(|(i, &cell)| (cell, i))
+            .collect();
+
+        let mut search = Enumeration {
+            cells: &cells,
+            constraints: constraints.iter()
+                .map(|constraint| (constraint.cells.iter().map(|cell| index[cell]).collect(), constraint.gophers))
+                .collect(),
+            cell_constraints: vec![Vec::new(); cells.len()],
+            assignment: vec![false; cells.len()],
+            placed: vec![0; constraints.len()],
+            unassigned: constraints.iter().map(|constraint| constraint.cells.len()).collect(),
+            budget: self.config.gophers().saturating_sub(self.flags_placed()),
+            max,
+            found: Vec::new(),
+        };
+
+        for (i, (constraint_cells, _)) in search.constraints.iter().enumerate() {
+            for &cell in constraint_cells {
+                search.cell_constraints[cell].push(i);
+            }
+        }
+
+        if search.run(0, 0) { Some(search.found) } else { None }
+    }
+
+    /// The chance that each unexposed, unflagged cell is safe, in row-major order.
+    /// Frontier placements are enumerated exactly and weighted by how many ways
+    /// the remaining gophers fit in the other cells; when there are too many
+    /// placements to enumerate, cells not decided by `certain_moves` fall back to
+    /// the overall density of the remaining gophers.
+    pub fn safety_estimates(&self) -> Vec<((usize, usize), f32)> {
+        let unknown = self.cells_where(|cell| !cell.is_exposed && !cell.is_flagged);
+        let budget = self.config.gophers().saturating_sub(self.flags_placed());
+
+        let gopher_chance = self.enumerated_gopher_chances(&unknown, budget).unwrap_or_else(|| {
+            let density = if unknown.is_empty() { 0.0 } else { (budget as f64 / unknown.len() as f64).min(1.0) };
+            let mut chances: HashMap<(usize, usize), f64> = unknown.iter().map(|&cell| (cell, density)).collect();
+            let certain = self.certain_moves();
+
+            chances.extend(certain.safe.into_iter().map(|cell| (cell, 0.0)));
+            chances.extend(certain.gophers.into_iter().map(|cell| (cell, 1.0)));
+            chances
+        });
+
+        unknown.into_iter()
+            .map(|cell| (cell, (1.0 - gopher_chance[&cell]) as f32))
+            .collect()
+    }
+
+    /// The unexposed, unflagged cell most likely to be safe according to
+    /// `safety_estimates`, preferring the first in row-major order on ties.
+    pub fn best_guess(&self) -> Option<(usize, usize)> {
+        self.safety_estimates()
+            .into_iter()
+            .fold(None, |best: Option<((usize, usize), f32)>, (cell, safety)| match best {
+                Some((_, best_safety)) if best_safety >= safety => best,
+                _ => Some((cell, safety)),
+            })
+            .map(|(cell, _)| cell)
+    }
+
+    /// For every frontier cell, in row-major order, the expected drop in the number
+    /// of consistent configurations from exposing it: the configurations are split
+    /// by what the cell would show (a gopher or the number it reveals) and each
+    /// outcome is weighted by its share of configurations. Unknown cells away from
+    /// the frontier are treated as safe when predicting numbers. Empty when there
+    /// are too many configurations to enumerate, using the same cap as
+    /// `safety_estimates`.
+    pub fn information_gain(&self) -> Vec<(usize, usize, f32)> {
+        self.information_gain_capped(MAX_ESTIMATED_CONFIGURATIONS)
+    }
+
+    /// Like `information_gain`, but empty once there are more than
+    /// `max_configurations` configurations to consider.
+    pub fn information_gain_capped(&self, max_configurations: usize) -> Vec<(usize, usize, f32)> {
+        let Some(configurations) = self.consistent_configurations(max_configurations) else { return Vec::new() };
+
+        if configurations.is_empty() {
+            return Vec::new();
+        }
+
+        let frontier = sorted(self.constraints().into_iter().flat_map(|constraint| constraint.cells).collect());
+        let total = configurations.len() as f32;
+
+        frontier.into_iter()
+            .map(|(x, y)| {
+                let neighbors = self.surrounding_cells_coords(x, y);
+                let flagged = neighbors.iter().filter(|&&(x, y)| self.field[y][x].is_flagged).count();
+                let mut outcomes: HashMap<Option<usize>, usize> = HashMap::new();
+
+                for configuration in &configurations {
+                    let outcome = if configuration.contains(&(x, y)) {
+                        None
+                    } else {
+                        Some(flagged + neighbors.iter().filter(|cell| configuration.contains(cell)).count())
+                    };
+
+                    *outcomes.entry(outcome).or_default() += 1;
+                }
+
+                let expected_remaining: f32 = outcomes.values().map(|&count| (count * count) as f32).sum::<f32>() / total;
+
+                (x, y, total - expected_remaining)
+            })
+            .collect()
+    }
+
+    fn enumerated_gopher_chances(&self, unknown: &[(usize, usize)], budget: usize) -> Option<HashMap<(usize, usize), f64>> {
+        let configurations = self.consistent_configurations(MAX_ESTIMATED_CONFIGURATIONS)?;
+
+        let frontier: HashSet<(usize, usize)> = self.constraints()
+            .into_iter()
+            .flat_map(|constraint| constraint.cells)
+            .collect();
+        let others = unknown.len() - frontier.len();
+
+        let log_weights: Vec<f64> = configurations.iter()
+            .map(|configuration| ln_choose(others, budget - configuration.len()))
+            .collect();
+        let heaviest = log_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        if heaviest == f64::NEG_INFINITY { return None }
+
+        let weights: Vec<f64> = log_weights.iter().map(|weight| (weight - heaviest).exp()).collect();
+        let total: f64 = weights.iter().sum();
+
+        let chances = unknown.iter().map(|cell| {
+            let expected: f64 = configurations.iter()
+                .zip(&weights)
+                .map(|(configuration, weight)| {
+                    let chance = if frontier.contains(cell) {
+                        configuration.contains(cell) as u8 as f64
+                    } else {
+                        (budget - configuration.len()) as f64 / others as f64
+                    };
+
+                    weight * chance
+                })
+                .sum();
+
+            (*cell, expected / total)
+        });
+
+        Some(chances.collect())
+    }
+
+    /// Plays until the game is won or a gopher is hit, returning the exposed cells
+    /// in order. Each round flags every certain gopher and exposes every certain
+    /// safe cell in row-major order; when nothing is certain it guesses the first
+    /// unexposed, unflagged cell in row-major order. The same board therefore
+    /// always produces the same sequence of moves.
+    pub fn autoplay(&mut self) -> Vec<(usize, usize)> {
+        let mut moves = Vec::new();
+
+        while self.remaining_cells > 0 {
+            let mut certain = self.certain_moves();
+            certain.safe.retain(|&(x, y)| !self.field[y][x].is_protected);
+
+            for (x, y) in certain.gophers {
+                if !self.field[y][x].is_flagged {
+                    self.toggle_flag(x, y);
+                }
+            }
+
+            let targets = if certain.safe.is_empty() {
+                self.next_guess().into_iter().collect()
+            } else {
+                certain.safe
+            };
+
+            if targets.is_empty() { break }
+
+            for (x, y) in targets {
+                let result = self.try_expose_cell(x, y);
+
+                if result != ExposeResult::WasAlreadyExposed {
+                    moves.push((x, y));
+                }
+
+                if result == ExposeResult::HasGopher { return moves }
+            }
+        }
+
+        moves
+    }
+
+    /// The safe cells from which the board can be cleared by deduction alone,
+    /// found by playing each one out on a clone. Empty while a deferred layout
+    /// has not been generated yet.
+    pub fn solvable_openings(&self) -> Vec<(usize, usize)> {
+        self.solvable_openings_sampled(usize::MAX)
+    }
+
+    /// Like `solvable_openings`, but tries at most `max_candidates` cells spread
+    /// evenly over the board to bound the cost on large boards.
+    pub fn solvable_openings_sampled(&self, max_candidates: usize) -> Vec<(usize, usize)> {
+        if !self.generated || max_candidates == 0 {
+            return Vec::new();
+        }
+
+        let candidates = self.cells_where(|cell| !cell.is_exposed && !cell.is_flagged && !cell.has_gopher());
+        let stride = candidates.len().div_ceil(max_candidates).max(1);
+
+        candidates.into_iter()
+            .step_by(stride)
+            .filter(|&(x, y)| {
+                let mut game = self.clone();
+                game.try_expose_cell(x, y);
+                game.solve_logically()
+            })
+            .collect()
+    }
+
+    /// The fraction of safe cells exposed after exposing `opening` on a clone and
+    /// deducing until stuck; 1.0 means no guess is needed from there, 0.0 that
+    /// `opening` is a gopher.
+    pub fn logic_solvable_fraction(&self, opening: (usize, usize)) -> f32 {
+        let mut game = self.clone();
+
+        if game.try_expose_cell(opening.0, opening.1) == ExposeResult::HasGopher {
+            return 0.0;
+        }
+
+        game.solve_logically();
+
+        let safe = game.total_safe_cells();
+        if safe == 0 { 1.0 } else { (safe - game.remaining_cells) as f32 / safe as f32 }
+    }
+
+    /// An estimate of how many guesses clearing the board takes: starting from
+    /// the current position (or the first opening of an untouched board), deduce
+    /// until stuck, then expose the safe cell `safety_estimates` rates safest and
+    /// count it as a guess. Player flags are ignored. 0 means no guess is needed;
+    /// also 0 while a deferred layout has not been generated yet. The estimates
+    /// fall back to plain densities on large frontiers, which keeps this bounded.
+    pub fn max_guess_depth(&self) -> usize {
+        if !self.generated { return 0 }
+
+        let mut game = self.clone();
+        game.reset_flags();
+
+        if game.exposed_count() == 0 {
+            let start = game.openings().first().map(|opening| opening[0])
+                .or_else(|| game.cells_where(|cell| !cell.has_gopher()).first().copied());
+
+            if let Some((x, y)) = start {
+                game.try_expose_cell(x, y);
+            }
+        }
+
+        let mut guesses = 0;
+
+        while game.loss.is_none() && !game.solve_logically() {
+            let guess = game.safety_estimates()
+                .into_iter()
+                .filter(|&((x, y), _)| !game.field[y][x].has_gopher() && !game.field[y][x].is_protected)
+                .reduce(|best, candidate| if candidate.1 > best.1 { candidate } else { best });
+
+            let Some(((x, y), _)) = guess else { break };
+
+            game.try_expose_cell(x, y);
+            guesses += 1;
+        }
+
+        guesses
+    }
+
+    /// Flags every certain gopher and exposes every certain safe cell, round after
+    /// round, until no certain move is left or a gopher is hit. Returns how many
+    /// cells were flagged or revealed, cascades included.
+    pub fn apply_certain_moves(&mut self) -> usize {
+        let remaining_before = self.remaining_cells;
+        let mut flagged = 0;
+
+        while self.remaining_cells > 0 {
+            let mut certain = self.certain_moves();
+            certain.safe.retain(|&(x, y)| !self.field[y][x].is_protected);
+
+            if certain.safe.is_empty() && certain.gophers.is_empty() { break }
+
+            for (x, y) in certain.gophers {
+                if !self.field[y][x].is_flagged {
+                    self.toggle_flag(x, y);
+                    flagged += 1;
+                }
+            }
+
+            for (x, y) in certain.safe {
+                if self.try_expose_cell(x, y) == ExposeResult::HasGopher {
+                    return flagged + remaining_before - self.remaining_cells;
+                }
+            }
+        }
+
+        flagged + remaining_before - self.remaining_cells
+    }
+
+    pub(crate) fn solve_logically(&mut self) -> bool {
+        self.apply_certain_moves();
+        self.remaining_cells == 0
+    }
+
+    fn next_guess(&self) -> Option<(usize, usize)> {
+        self.cells_where(|cell| !cell.is_exposed && !cell.is_flagged && !cell.is_protected).into_iter().next()
+    }
+
+    /// For every cell, indexed `[y][x]`, how many of `constraints` involve it; 0
+    /// for cells off the frontier.
+    pub fn constraint_degree(&self) -> Vec<Vec<u8>> {
+        let (width, height) = self.config.size();
+        let mut result = vec![vec![0u8; width]; height];
+
+        for constraint in self.constraints() {
+            for (x, y) in constraint.cells {
+                result[y][x] += 1;
+            }
+        }
+
+        result
+    }
+
+    /// One constraint per exposed number that still borders unexposed, unflagged
+    /// cells, with flagged neighbors already subtracted from the count. Numbers
+    /// that are over-flagged or can no longer be satisfied are left out.
+    pub fn constraints(&self) -> Vec<Constraint> {
+        let (width, height) = self.config.size();
+        let mut result = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = &self.field[y][x];
+
+                if !cell.is_exposed || cell.has_gopher() {
+                    continue;
+                }
+
+                let mut cells = Vec::new();
+                let mut flagged = 0;
+
+                for (nx, ny) in self.surrounding_cells_coords(x, y) {
+                    let neighbor = &self.field[ny][nx];
+
+                    if neighbor.is_exposed {
+                        continue;
+                    }
+
+                    if neighbor.is_flagged {
+                        flagged += 1;
+                    } else {
+                        cells.push((nx, ny));
+                    }
+                }
+
+                let number = cell.surrounding_gophers as usize;
+
+                if cells.is_empty() || flagged > number || number - flagged > cells.len() {
+                    continue;
+                }
+
+                cells.sort_by_key(|&(x, y)| (y, x));
+
+                result.push(Constraint { origin: (x, y), cells, gophers: number - flagged });
+            }
+        }
+
+        result
+    }
+}
+
+fn mark_if_determined(
+    cells: &[(usize, usize)],
+    gophers: usize,
+    safe: &mut HashSet<(usize, usize)>,
+    known_gophers: &mut HashSet<(usize, usize)>,
+) -> bool {
+    let target = if gophers == 0 {
+        safe
+    } else if gophers == cells.len() {
+        known_gophers
+    } else {
+        return false;
+    };
+
+    let mut changed = false;
+
+    for &cell in cells {
+        changed |= target.insert(cell);
+    }
+
+    changed
+}
+
+fn ln_choose(n: usize, k: usize) -> f64 {
+    if k > n { return f64::NEG_INFINITY }
+
+    (0..k).map(|i| ((n - i) as f64 / (i + 1) as f64).ln()).sum()
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 { word.to_string() } else { format!("{word}s") }
+}
+
+fn sorted(cells: HashSet<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = cells.into_iter().collect();
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells
+}
+
+struct Enumeration<'a> {
+    cells: &'a [(usize, usize)],
+    constraints: Vec<(Vec<usize>, usize)>,
+    cell_constraints: Vec<Vec<usize>>,
+    assignment: Vec<bool>,
+    placed: Vec<usize>,
+    unassigned: Vec<usize>,
+    budget: usize,
+    max: usize,
+    found: Vec<HashSet<(usize, usize)>>,
+}
+
+impl Enumeration<'_> {
+    fn run(&mut self, cell: usize, gophers: usize) -> bool {
+        if cell == self.cells.len() {
+            if self.found.len() == self.max { return false }
+
+            self.found.push(
+                (0..self.cells.len())
+                    .filter(|&i| self.assignment[i])
+                    .map(|i| self.cells[i])
+                    .collect(),
+            );
+
+            return true;
+        }
+
+        for has_gopher in [false, true] {
+            if has_gopher && gophers == self.budget {
+                continue;
+            }
+
+            self.assignment[cell] = has_gopher;
+
+            let consistent = self.cell_constraints[cell].iter().all(|&i| {
+                let placed = self.placed[i] + has_gopher as usize;
+                let required = self.constraints[i].1;
+
+                placed <= required && placed + self.unassigned[i] > required
+            });
+
+            if !consistent {
+                continue;
+            }
+
+            for &i in &self.cell_constraints[cell] {
+                self.placed[i] += has_gopher as usize;
+                self.unassigned[i] -= 1;
+            }
+
+            let completed = self.run(cell + 1, gophers + has_gopher as usize);
+
+            for &i in &self.cell_constraints[cell] {
+                self.placed[i] -= has_gopher as usize;
+                self.unassigned[i] += 1;
+            }
+
+            if !completed { return false }
+        }
+
+        self.assignment[cell] = false;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{board, set_gopher_total};
+    use crate::{Difficulty, FieldSize, GameConfig, GameState, LossReason};
+
+    #[test]
+    fn a_fifty_fifty_is_stuck_until_a_flag_decides_it() {
+        let mut game = board(&["...*", "...."]);
+        game.try_expose_cell(0, 0);
+
+        assert!(game.is_stuck());
+        assert_eq!(game.certain_moves(), CertainMoves::default());
+
+        game.toggle_flag(3, 1);
+        assert!(!game.is_stuck());
+        assert_eq!(game.certain_moves(), CertainMoves { safe: vec![(3, 0)], gophers: vec![] });
+    }
+
+    #[test]
+    fn subset_deductions_resolve_a_one_two_one() {
+        let mut game = board(&[".*.*.", ".....", "....."]);
+        game.try_expose_cell(0, 2);
+
+        assert!(!game.is_stuck());
+        assert_eq!(game.certain_moves(), CertainMoves { safe: vec![(0, 0), (2, 0), (4, 0)], gophers: vec![(1, 0), (3, 0)] });
+    }
+
+    #[test]
+    fn a_cleared_board_is_not_stuck() {
+        let mut game = board(&["*."]);
+        game.try_expose_cell(1, 0);
+
+        assert!(!game.is_stuck());
+    }
+
+    fn sorted_configurations(game: &GopherSweeper, max: usize) -> Option<Vec<Vec<(usize, usize)>>> {
+        let mut result: Vec<Vec<(usize, usize)>> = game.consistent_configurations(max)?
+            .into_iter()
+            .map(sorted)
+            .collect();
+        result.sort();
+
+        Some(result)
+    }
+
+    #[test]
+    fn enumeration_respects_the_gopher_total_and_the_cap() {
+        let mut game = board(&[".*..", "...*", "...."]);
+        game.try_expose_cell(0, 2);
+
+        assert_eq!(sorted_configurations(&game, 10), Some(vec![vec![(1, 0), (3, 1)], vec![(1, 0), (3, 2)]]));
+        assert_eq!(sorted_configurations(&game, 2).map(|configurations| configurations.len()), Some(2));
+        assert_eq!(sorted_configurations(&game, 1), None);
+
+        set_gopher_total(&mut game, 3);
+        assert_eq!(sorted_configurations(&game, 10).unwrap(), vec![
+            vec![(0, 0), (3, 0), (3, 1)],
+            vec![(0, 0), (3, 0), (3, 2)],
+            vec![(1, 0), (3, 1)],
+            vec![(1, 0), (3, 2)],
+        ]);
+    }
+
+    fn explained(cell: (usize, usize), kind: MoveKind, rationale: &str) -> Option<ExplainedMove> {
+        Some(ExplainedMove { cell, kind, rationale: rationale.to_string() })
+    }
+
+    #[test]
+    fn explained_gophers_count_the_unexposed_and_flagged_neighbors() {
+        let mut game = board(&["*..", "*..", "..."]);
+        game.try_expose_cell(2, 2);
+
+        assert_eq!(game.next_explained_move(), explained((0, 0), MoveKind::Gopher, "cell (1, 0) shows 2 with 2 unexposed neighbors, so (0, 0) is a gopher"));
+
+        game.toggle_flag(0, 0);
+        assert_eq!(
+            game.next_explained_move(),
+            explained((0, 1), MoveKind::Gopher, "cell (1, 0) shows 2 with 1 flag and 1 other unexposed neighbor, so (0, 1) is a gopher"),
+        );
+    }
+
+    #[test]
+    fn explained_safe_cells_name_the_subset_or_the_flags() {
+        let mut game = board(&[".*.*.", ".....", "....."]);
+        game.try_expose_cell(0, 2);
+
+        assert_eq!(game.next_explained_move(), explained(
+            (2, 0),
+            MoveKind::Safe,
+            "cell (0, 1) needs 1 gopher among neighbors it shares with cell (1, 1), which needs 1 in total, so (2, 0) is safe",
+        ));
+
+        game.toggle_flag(1, 0);
+        assert_eq!(game.next_explained_move(), explained((0, 0), MoveKind::Safe, "cell (0, 1) shows 1 and already has 1 flagged neighbor, so (0, 0) is safe"));
+    }
+
+    #[test]
+    fn a_guess_has_no_explanation() {
+        let mut game = board(&["...*", "...."]);
+        game.try_expose_cell(0, 0);
+
+        assert_eq!(game.next_explained_move(), None);
+    }
+
+    #[test]
+    fn autoplay_guesses_the_first_open_cell_in_row_major_order() {
+        let mut game = board(&["..", "..", ".*"]);
+        assert_eq!(game.autoplay(), vec![(0, 0), (0, 2)]);
+        assert_eq!(game.remaining_cells, 0);
+
+        let mut game = board(&["..", "..", ".*"]);
+        game.toggle_flag(0, 0);
+        assert_eq!(game.autoplay(), vec![(1, 0), (0, 2), (1, 2)]);
+        assert_eq!(game.state(), GameState::Lost(LossReason::HitGopher));
+    }
+
+    #[test]
+    fn autoplay_repeats_on_the_same_seed() {
+        let config = || GameConfig::new(FieldSize::Medium, Difficulty::Hard);
+
+        for seed in 0..5 {
+            let first = GopherSweeper::new_seeded(config(), seed).autoplay();
+            let second = GopherSweeper::new_seeded(config(), seed).autoplay();
+
+            assert!(!first.is_empty());
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn constraints_subtract_flags_from_each_number() {
+        let mut game = board(&["..2.", "....", "*..."]);
+        game.try_expose_cell(0, 0);
+
+        let constraint = |origin, cells: &[(usize, usize)], gophers| Constraint { origin, cells: cells.to_vec(), gophers };
+
+        assert_eq!(game.constraints(), vec![
+            constraint((1, 0), &[(2, 0), (2, 1)], 2),
+            constraint((0, 1), &[(0, 2), (1, 2)], 1),
+            constraint((1, 1), &[(2, 0), (2, 1), (0, 2), (1, 2), (2, 2)], 3),
+        ]);
+
+        game.toggle_flag(2, 0);
+        game.toggle_flag(2, 1);
+
+        assert_eq!(game.constraints(), vec![
+            constraint((0, 1), &[(0, 2), (1, 2)], 1),
+            constraint((1, 1), &[(0, 2), (1, 2), (2, 2)], 1),
+        ]);
+
+        game.toggle_flag(0, 2);
+        game.toggle_flag(1, 2);
+
+        assert_eq!(game.constraints(), vec![]);
+    }
+
+    #[test]
+    fn frontier_holds_both_sides_of_the_border() {
+        let mut game = board(&["..2.", "....", "*..."]);
+        assert_eq!(game.frontier(), Frontier::default());
+
+        game.try_expose_cell(0, 0);
+        game.toggle_flag(2, 0);
+        let frontier = game.frontier();
+
+        assert_eq!(frontier.exposed, HashSet::from([(1, 0), (0, 1), (1, 1)]));
+        assert_eq!(frontier.unexposed, HashSet::from([(2, 0), (2, 1), (0, 2), (1, 2), (2, 2)]));
+    }
+
+    #[test]
+    fn solvable_openings_are_the_cells_that_clear_without_guessing() {
+        let mut game = board(&["*..", "...", "..."]);
+        assert_eq!(game.solvable_openings(), vec![(2, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(game.solvable_openings_sampled(2), vec![(2, 1)]);
+        assert_eq!(game.solvable_openings_sampled(0), vec![]);
+
+        game.toggle_flag(2, 0);
+        assert_eq!(game.solvable_openings(), vec![]);
+        assert!(game.cells_where(|cell| cell.is_exposed).is_empty());
+
+        assert_eq!(board(&["*.", ".."]).solvable_openings(), vec![]);
+
+        let config = GameConfig::new(FieldSize::Small, Difficulty::Easy).with_safe_radius(1);
+        assert_eq!(GopherSweeper::new_seeded(config, 0).solvable_openings(), vec![]);
+    }
+
+    #[test]
+    fn over_flagged_numbers_are_unsatisfied() {
+        let mut game = board(&["*.*", "...", "..."]);
+        game.try_expose_cell(1, 2);
+        game.toggle_flag(1, 0);
+        assert_eq!(game.unsatisfied_numbers(), vec![]);
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.unsatisfied_numbers(), vec![(0, 1)]);
+
+        game.toggle_flag(2, 0);
+        assert_eq!(game.unsatisfied_numbers(), vec![(0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn heavy_neighbors_can_satisfy_a_number_alone() {
+        let mut game = board(&["2..", "...", "..."]);
+        game.try_expose_cell(2, 2);
+        assert_eq!(game.unsatisfied_numbers(), vec![(1, 0), (0, 1), (1, 1)]);
+
+        game.config.max_gophers_per_cell = 2;
+        assert_eq!(game.unsatisfied_numbers(), vec![]);
+    }
+
+    #[test]
+    fn forced_moves_skip_cells_that_are_already_flagged() {
+        let mut game = board(&["*.*", "...", "..."]);
+        assert_eq!(game.forced_move_count(), 0);
+
+        game.try_expose_cell(1, 2);
+        assert_eq!(game.forced_move_count(), 3);
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.forced_move_count(), 2);
+
+        game.toggle_flag(2, 0);
+        game.try_expose_cell(1, 0);
+        assert_eq!(game.forced_move_count(), 0);
+    }
+
+    #[test]
+    fn safety_estimates_count_the_gophers_left_for_the_unseen_cells() {
+        let mut game = board(&["*...", "....", "...."]);
+        game.try_expose_cell(1, 1);
+        let safety = |game: &GopherSweeper, cell| game.safety_estimates().into_iter().find(|&(c, _)| c == cell).unwrap().1;
+
+        assert_eq!(game.safety_estimates().len(), 11);
+        assert_eq!(safety(&game, (2, 2)), 0.875);
+        assert_eq!(safety(&game, (3, 1)), 1.0);
+        assert_eq!(game.forced_move_count(), 0);
+        assert_eq!(game.best_guess(), Some((3, 0)));
+
+        set_gopher_total(&mut game, 2);
+        assert!((safety(&game, (3, 1)) - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(game.best_guess(), Some((0, 0)));
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.safety_estimates().len(), 10);
+        assert_eq!(game.best_guess(), Some((1, 0)));
+    }
+
+    #[test]
+    fn best_guess_needs_an_open_cell() {
+        let mut game = board(&["*.", ".."]);
+        game.try_expose_cell(1, 1);
+        game.toggle_flag(1, 0);
+        game.toggle_flag(0, 1);
+        assert_eq!(game.best_guess(), Some((0, 0)));
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.best_guess(), None);
+    }
+
+    #[test]
+    fn information_gain_matches_a_hand_count() {
+        let mut game = board(&["*...", "....", "...."]);
+        game.try_expose_cell(1, 1);
+
+        // One gopher among the eight cells around the 1: exposing a corner splits the
+        // eight placements 1/2/5 by outcome and an edge cell splits them 1/4/3.
+        let corner = 8.0 - (1 + 4 + 25) as f32 / 8.0;
+        let edge = 8.0 - (1 + 16 + 9) as f32 / 8.0;
+
+        assert_eq!(game.information_gain(), vec![
+            (0, 0, corner), (1, 0, edge), (2, 0, corner),
+            (0, 1, edge), (2, 1, edge),
+            (0, 2, corner), (1, 2, edge), (2, 2, corner),
+        ]);
+        assert_eq!(game.information_gain_capped(8), game.information_gain());
+        assert_eq!(game.information_gain_capped(7), vec![]);
+
+        game.toggle_flag(2, 0);
+        let gains = game.information_gain();
+
+        assert_eq!(gains.len(), 7);
+        assert!(gains.iter().all(|&(x, y, gain)| (x, y) != (2, 0) && gain == 0.0));
+    }
+
+    #[test]
+    fn a_fifty_fifty_is_a_forced_guess() {
+        let mut game = board(&["*.", "..", ".."]);
+        game.try_expose_cell(0, 2);
+        assert_eq!(game.remaining_cells, 1);
+        assert!(game.is_forced_guess());
+
+        let mut deducible = board(&["*..", "*..", "..."]);
+        deducible.try_expose_cell(2, 2);
+        assert!(!deducible.cell(0, 2).is_exposed);
+        assert!(!deducible.is_forced_guess());
+
+        deducible.try_expose_cell(0, 2);
+        assert_eq!(deducible.remaining_cells, 0);
+        assert!(!deducible.is_forced_guess());
+    }
+
+    #[test]
+    fn a_misplaced_flag_shows_up_as_contradicting_pairs() {
+        let mut game = board(&[".*.", "..."]);
+        game.try_expose_cell(2, 0);
+        game.try_expose_cell(0, 1);
+        game.try_expose_cell(2, 1);
+        assert!(game.contradictions().is_empty());
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.contradictions(), vec![vec![(2, 0), (0, 1)], vec![(0, 1), (2, 1)]]);
+
+        game.toggle_flag(0, 0);
+        game.toggle_flag(1, 0);
+        assert!(game.contradictions().is_empty());
+
+        game.toggle_flag(1, 1);
+        assert_eq!(game.contradictions(), vec![vec![(2, 0)], vec![(0, 1)], vec![(2, 1)]]);
+    }
+
+    #[test]
+    fn logic_solvable_fraction_measures_deducible_progress() {
+        let game = board(&["*..*", "....", "...*"]);
+        assert_eq!(game.logic_solvable_fraction((0, 2)), 1.0);
+        assert_eq!(game.logic_solvable_fraction((3, 0)), 0.0);
+        assert_eq!(game.remaining_cells, 9);
+
+        let fifty_fifty = board(&["*.", "..", ".."]);
+        assert_eq!(fifty_fifty.logic_solvable_fraction((0, 2)), 0.8);
+    }
+
+    #[test]
+    fn constraint_degree_counts_the_numbers_on_each_cell() {
+        let mut game = board(&["*..*.", "....."]);
+        game.try_expose_cell(2, 0);
+        game.try_expose_cell(2, 1);
+        assert_eq!(game.constraint_degree(), vec![vec![0, 2, 0, 2, 0], vec![0, 2, 0, 2, 0]]);
+
+        game.try_expose_cell(4, 1);
+        game.toggle_flag(3, 0);
+        assert_eq!(game.constraint_degree(), vec![vec![0, 2, 0, 0, 1], vec![0, 2, 0, 3, 0]]);
+    }
+
+    #[test]
+    fn each_fifty_fifty_takes_one_guess() {
+        assert_eq!(board(&["*..*", "....", "...*"]).max_guess_depth(), 0);
+        assert_eq!(board(&["*.", "..", ".."]).max_guess_depth(), 1);
+
+        let mut game = board(&["*.", "..", "..", "..", ".*"]);
+        assert_eq!(game.max_guess_depth(), 2);
+
+        game.toggle_flag(0, 0);
+        assert_eq!(game.max_guess_depth(), 2);
+
+        game.try_expose_cell(0, 2);
+        game.try_expose_cell(1, 0);
+        assert_eq!(game.max_guess_depth(), 1);
+    }
+
+    #[test]
+    fn certain_moves_are_applied_until_none_is_left() {
+        let mut game = board(&["*..", "*..", "..."]);
+        game.try_expose_cell(2, 2);
+
+        assert_eq!(game.apply_certain_moves(), 3);
+        assert_eq!(game.cells_where(|cell| cell.is_flagged), vec![(0, 0), (0, 1)]);
+        assert_eq!(game.remaining_cells, 0);
+        assert_eq!(game.apply_certain_moves(), 0);
+
+        let mut fifty_fifty = board(&["*.", "..", ".."]);
+        fifty_fifty.try_expose_cell(0, 2);
+        assert_eq!(fifty_fifty.apply_certain_moves(), 0);
+        assert_eq!(fifty_fifty.flags_placed(), 0);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{GopherSweeper, VisibleCell};
+use std::fmt::Write;
+
+const NUMBER_COLORS: [&str; 8] = ["#0000ff", "#008000", "#ff0000", "#000080", "#800000", "#008080", "#000000", "#808080"];
+
+impl GopherSweeper {
+    /// A standalone SVG of the board with one `<rect>` per cell, `cell_px` pixels
+    /// square, plus a `<text>` for numbers, flags and exposed gophers. With
+    /// `reveal`, gophers under unexposed cells are drawn too.
+    pub fn to_svg(&self, cell_px: u32, reveal: bool) -> String {
+        let (width, height) = self.config.size();
+        let size = cell_px as usize;
+        let mut result = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central">"#,
+            width * size,
+            height * size,
+            size * 3 / 4,
+        );
+
+        for y in 0..height {
+            for x in 0..width {
+                let visible = self.visible_cell(x, y);
+
+                let fill = match visible {
+                    VisibleCell::Exposed(_) => "#e0e0e0",
+                    VisibleCell::Gopher => "#ff8080",
+                    VisibleCell::Obscured => "#606060",
+                    VisibleCell::Hidden | VisibleCell::Flagged => "#a0a0a0",
+                };
+
+                let glyph = match visible {
+                    VisibleCell::Flagged => Some(("F".to_string(), "#ff0000")),
+                    VisibleCell::Exposed(number) if number > 0 => {
+                        Some((number.to_string(), NUMBER_COLORS[(number as usize - 1).min(7)]))
+                    }
+                    VisibleCell::Gopher => Some(("G".to_string(), "#000000")),
+                    _ if reveal && self.field[y][x].has_gopher() => Some(("G".to_string(), "#000000")),
+                    _ => None,
+                };
+
+                let (left, top) = (x * size, y * size);
+                let _ = write!(result, r##"<rect x="{left}" y="{top}" width="{size}" height="{size}" fill="{fill}" stroke="#808080"/>"##);
+
+                if let Some((text, color)) = glyph {
+                    let _ = write!(result, r#"<text x="{}" y="{}" fill="{color}">{text}</text>"#, left + size / 2, top + size / 2);
+                }
+            }
+        }
+
+        result.push_str("</svg>");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::board;
+
+    #[test]
+    fn every_cell_gets_a_rect() {
+        let mut game = board(&["*..", "..2"]);
+        game.toggle_flag(2, 1);
+        game.try_expose_cell(1, 0);
+        let svg = game.to_svg(20, false);
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert!(svg.contains(r#"width="60" height="40""#));
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert!(svg.contains(r##"<text x="30" y="10" fill="#ff0000">3</text>"##));
+        assert!(svg.contains(r##"<text x="50" y="30" fill="#ff0000">F</text>"##));
+        assert!(!svg.contains(">G<"));
+    }
+
+    #[test]
+    fn revealing_draws_only_the_unflagged_gophers() {
+        let mut game = board(&["*..", "..2"]);
+        game.toggle_flag(2, 1);
+        let svg = game.to_svg(20, true);
+
+        assert_eq!(svg.matches(">G<").count(), 1);
+        assert!(svg.contains(r##"<text x="10" y="10" fill="#000000">G</text>"##));
+        assert_eq!(svg.matches(">F<").count(), 1);
+    }
+}
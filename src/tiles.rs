@@ -0,0 +1,150 @@
+use crate::{fnv1a, Adjacency, Cell, Difficulty, FieldSize, GameConfig, GopherSweeper};
+
+/// An unbounded board that is a function of its seed: whether a cell holds a
+/// gopher depends only on the seed and the cell's coordinates, so any region can
+/// be generated on demand without storing the rest. The board starts at `(0, 0)`
+/// and extends indefinitely to the right and downwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileGenerator {
+    seed: u64,
+    gophers_percentage: f32,
+    adjacency: Adjacency,
+}
+
+impl TileGenerator {
+    pub fn new(seed: u64, gophers_percentage: f32) -> Self {
+        TileGenerator {
+            seed,
+            gophers_percentage,
+            adjacency: Adjacency::Standard,
+        }
+    }
+
+    pub fn with_adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn gophers_percentage(&self) -> f32 {
+        self.gophers_percentage
+    }
+
+    pub fn adjacency(&self) -> Adjacency {
+        self.adjacency
+    }
+
+    pub fn has_gopher(&self, x: usize, y: usize) -> bool {
+        let mut bytes = [0; 24];
+        bytes[..8].copy_from_slice(&self.seed.to_le_bytes());
+        bytes[8..16].copy_from_slice(&(x as u64).to_le_bytes());
+        bytes[16..].copy_from_slice(&(y as u64).to_le_bytes());
+
+        let sample = (fnv1a(&bytes) >> 40) as f32 / (1u64 << 24) as f32;
+        sample < self.gophers_percentage
+    }
+
+    /// The cells of the `width` by `height` rectangle whose top left corner is at
+    /// `(x, y)`, indexed `[y][x]` relative to that corner. Numbers count every
+    /// neighbor on the unbounded board, including those outside the tile, so
+    /// overlapping tiles always agree.
+    pub fn tile(&self, x: usize, y: usize, width: usize, height: usize) -> Vec<Vec<Cell>> {
+        (y..y + height)
+            .map(|y| (x..x + width).map(|x| self.cell(x, y)).collect())
+            .collect()
+    }
+
+    /// A regular game over the `width` by `height` region at the origin. Unlike
+    /// [`tile`](Self::tile), the numbers on its right and bottom edges only count
+    /// the gophers inside the game.
+    pub fn materialize(&self, width: usize, height: usize) -> GopherSweeper {
+        let gophers = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.has_gopher(x, y))
+            .count();
+
+        let config = GameConfig::new(FieldSize::Custom { width, height }, Difficulty::Exact { gophers })
+            .with_adjacency(self.adjacency);
+
+        let mut result = GopherSweeper::empty(config, Some(self.seed));
+
+        for (y, row) in result.field.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                cell.gopher_count = self.has_gopher(x, y) as u8;
+            }
+        }
+
+        result.recompute_counts();
+        result.generated = true;
+
+        result
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        let surrounding_gophers = GopherSweeper::neighbor_offsets(self.adjacency)
+            .iter()
+            .filter_map(|&(dx, dy)| Some((x.checked_add_signed(dx as isize)?, y.checked_add_signed(dy as isize)?)))
+            .filter(|&(x, y)| self.has_gopher(x, y))
+            .count() as u8;
+
+        Cell {
+            gopher_count: self.has_gopher(x, y) as u8,
+            surrounding_gophers,
+            ..Cell::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_tiles_agree() {
+        let generator = TileGenerator::new(7, 0.2);
+        let big = generator.tile(0, 0, 20, 20);
+        let small = generator.tile(10, 5, 20, 20);
+
+        assert_eq!(generator.tile(0, 0, 3, 2)[1][2], big[1][2]);
+
+        for y in 0..15 {
+            for x in 0..10 {
+                assert_eq!(big[y + 5][x + 10], small[y][x]);
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_match_a_materialized_board() {
+        for adjacency in [Adjacency::Standard, Adjacency::Diagonal] {
+            let generator = TileGenerator::new(7, 0.2).with_adjacency(adjacency);
+            let tile = generator.tile(0, 0, 12, 10);
+            let game = generator.materialize(12, 10);
+
+            assert!(game.cells_where(Cell::has_gopher).len() > 5);
+            assert_eq!(game.cells_where(Cell::has_gopher).len(), game.config.gophers());
+
+            for (y, row) in tile.iter().enumerate() {
+                for (x, cell) in row.iter().enumerate() {
+                    assert_eq!(cell.gopher_count, game.field[y][x].gopher_count);
+
+                    if x < 11 && y < 9 {
+                        assert_eq!(cell.surrounding_gophers, game.field[y][x].surrounding_gophers);
+                    } else {
+                        assert!(cell.surrounding_gophers >= game.field[y][x].surrounding_gophers);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_density_bounds_give_empty_and_full_boards() {
+        assert!(TileGenerator::new(3, 0.0).tile(0, 0, 8, 8).iter().flatten().all(|cell| !cell.has_gopher()));
+        assert!(TileGenerator::new(3, 1.0).tile(5, 5, 8, 8).iter().flatten().all(|cell| cell.surrounding_gophers == 8));
+        assert_eq!(TileGenerator::new(3, 1.0).tile(0, 0, 2, 2)[0][0].surrounding_gophers, 3);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{Field, FieldSize, GopherSweeper};
+
+impl GopherSweeper {
+    /// Rotates the board a quarter turn clockwise. Non-square boards swap width
+    /// and height, which turns the config's field size into a custom one.
+    pub fn rotate_cw(&mut self) {
+        let (_, height) = self.config.size();
+        self.transform(true, |x, y| (height - 1 - y, x));
+    }
+
+    pub fn rotate_ccw(&mut self) {
+        let (width, _) = self.config.size();
+        self.transform(true, |x, y| (y, width - 1 - x));
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        let (width, _) = self.config.size();
+        self.transform(false, |x, y| (width - 1 - x, y));
+    }
+
+    pub fn flip_vertical(&mut self) {
+        let (_, height) = self.config.size();
+        self.transform(false, |x, y| (x, height - 1 - y));
+    }
+
+    fn transform(&mut self, swaps_dimensions: bool, destination: impl Fn(usize, usize) -> (usize, usize)) {
+        self.clear_transients();
+
+        let (width, height) = self.config.size();
+        let (new_width, new_height) = if swaps_dimensions { (height, width) } else { (width, height) };
+
+        let mut field = Field::new(new_width, new_height);
+
+        for (y, row) in self.field.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let (nx, ny) = destination(x, y);
+                field[ny][nx] = std::mem::take(cell);
+            }
+        }
+
+        self.field = field;
+
+        if swaps_dimensions && width != height {
+            self.config.field_size = FieldSize::Custom { width: new_width, height: new_height };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::board;
+    use crate::{Cell, Difficulty, GameConfig};
+
+    fn cells(game: &GopherSweeper) -> Vec<Cell> {
+        game.field.iter().flatten().cloned().collect()
+    }
+
+    #[test]
+    fn four_quarter_turns_are_the_identity() {
+        let mut game = board(&["*...", "..2.", "...."]);
+        game.try_expose_cell(0, 2);
+        game.toggle_flag(3, 0);
+        let before = cells(&game);
+
+        game.rotate_cw();
+        assert_eq!(game.config.size(), (3, 4));
+        assert!(game.field[3][2].is_flagged);
+        assert!(game.field[0][2].has_gopher());
+        assert_eq!(game.field[2][1].gopher_count, 2);
+
+        let rotated = cells(&game);
+        game.recompute_counts();
+        assert_eq!(cells(&game), rotated);
+
+        for _ in 0..3 {
+            game.rotate_cw();
+        }
+
+        assert_eq!(game.config.size(), (4, 3));
+        assert_eq!(cells(&game), before);
+    }
+
+    #[test]
+    fn flips_and_counter_rotations_undo_themselves() {
+        let mut game = board(&["*...", "..2.", "...."]);
+        game.toggle_flag(3, 0);
+        let before = cells(&game);
+
+        game.flip_horizontal();
+        assert!(game.field[0][3].has_gopher());
+        assert!(game.field[0][0].is_flagged);
+        game.flip_horizontal();
+        game.flip_vertical();
+        assert_eq!(game.field[1][2].gopher_count, 2);
+        assert!(game.field[2][0].has_gopher());
+        game.flip_vertical();
+        game.rotate_cw();
+        game.rotate_ccw();
+
+        assert_eq!(cells(&game), before);
+    }
+
+    #[test]
+    fn square_boards_keep_their_field_size() {
+        let mut game = GopherSweeper::new(GameConfig::new(FieldSize::Small, Difficulty::Easy));
+        game.rotate_cw();
+        assert_eq!(game.config.size(), (8, 10));
+
+        let mut game = board(&["*..", "...", "..."]);
+        let size = game.config.field_size;
+        game.rotate_ccw();
+
+        assert_eq!(game.config.field_size, size);
+        assert!(game.field[2][0].has_gopher());
+    }
+}